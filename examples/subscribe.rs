@@ -142,12 +142,28 @@ fn handle_events(
                     println!("Received packet: {packet_type}");
                 }
             },
+            mqtt::connection::Event::NotifyPacketReceivedEx {
+                packet,
+                auto_responded,
+            } => {
+                let packet_type = packet.packet_type();
+                println!("Received packet: {packet_type} (auto responded: {auto_responded})");
+            }
             mqtt::connection::Event::NotifyPacketIdReleased(packet_id) => {
                 println!("Packet ID {packet_id} released");
             }
+            mqtt::connection::Event::NotifyStoreEmpty => {
+                println!("All stored packets acknowledged");
+            }
             mqtt::connection::Event::NotifyError(error) => {
                 eprintln!("MQTT Error: {error:?}");
             }
+            mqtt::connection::Event::NotifyWarning(error) => {
+                eprintln!("MQTT Warning: {error:?}");
+            }
+            mqtt::connection::Event::NotifyErrorContext(context) => {
+                eprintln!("MQTT Error context: {context:?}");
+            }
             mqtt::connection::Event::RequestClose => {
                 println!("Connection close requested");
                 return Ok(());
@@ -20,6 +20,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
 use core::fmt::Debug;
 
 use num_traits::{One, PrimInt};
@@ -166,7 +167,53 @@ where
         }
     }
 
+    pub fn use_range(&mut self, low: T, high: T) -> bool {
+        if high < low || low < self.lowest || self.highest < high {
+            return false;
+        }
+        // The whole range must be vacant, so it must fit in a single interval
+        let Some(iv) = self.pool.get(&ValueInterval::new_single(low)).cloned() else {
+            return false;
+        };
+        if iv.high < high {
+            return false;
+        }
+        self.pool.remove(&iv);
+        if iv.low < low {
+            self.pool
+                .insert(ValueInterval::new_range(iv.low, low - T::one()));
+        }
+        if high < iv.high {
+            self.pool
+                .insert(ValueInterval::new_range(high + T::one(), iv.high));
+        }
+        true
+    }
+
+    pub fn used_ranges(&self) -> Vec<(T, T)> {
+        let mut ranges = Vec::new();
+        // Next value not covered yet, `None` once `highest` is reached
+        let mut next = Some(self.lowest);
+        for iv in &self.pool {
+            let Some(n) = next else {
+                break;
+            };
+            if n < iv.low {
+                ranges.push((n, iv.low - T::one()));
+            }
+            next = (iv.high < self.highest).then(|| iv.high + T::one());
+        }
+        if let Some(n) = next {
+            ranges.push((n, self.highest));
+        }
+        ranges
+    }
+
     pub fn is_used(&self, value: T) -> bool {
+        // Values outside [lowest, highest] can never be allocated
+        if value < self.lowest || self.highest < value {
+            return false;
+        }
         !self.pool.iter().any(|iv| iv.contains(value))
     }
 
@@ -27,10 +27,13 @@ use core::marker::PhantomData;
 
 use crate::mqtt::common::tracing::{error, info, trace, warn};
 use crate::mqtt::common::Cursor;
+use crate::mqtt::common::HashMap;
 use crate::mqtt::common::HashSet;
-use crate::mqtt::connection::event::{GenericEvent, TimerKind};
+use crate::mqtt::common::IntoPayload;
+use crate::mqtt::connection::event::{
+    Direction, DisconnectCause, GenericEvent, MqttErrorContext, TimerKind,
+};
 use crate::mqtt::connection::GenericStore;
-use crate::mqtt::result_code;
 
 use serde::Serialize;
 
@@ -48,26 +51,42 @@ use crate::mqtt::connection::packet_builder::{
 };
 use crate::mqtt::connection::packet_id_manager::PacketIdManager;
 use crate::mqtt::connection::role;
-use crate::mqtt::connection::role::RoleType;
+use crate::mqtt::connection::role::{ConnectionRole, RoleType};
 use crate::mqtt::connection::sendable::Sendable;
 use crate::mqtt::connection::version::*;
 use crate::mqtt::packet::v3_1_1;
 use crate::mqtt::packet::v5_0;
 use crate::mqtt::packet::GenericPacket;
 use crate::mqtt::packet::GenericStorePacket;
+use crate::mqtt::packet::IntoPacketId;
 use crate::mqtt::packet::IsPacketId;
+use crate::mqtt::packet::PacketType;
 use crate::mqtt::packet::Qos;
 use crate::mqtt::packet::ResponsePacket;
-use crate::mqtt::packet::{Property, TopicAliasRecv, TopicAliasSend};
+use crate::mqtt::packet::{
+    Properties, PropertiesParse, Property, ReasonString, ServerKeepAlive, TopicAliasRecv,
+    TopicAliasSend,
+};
 use crate::mqtt::prelude::GenericPacketTrait;
 use crate::mqtt::result_code::{
-    ConnectReasonCode, ConnectReturnCode, DisconnectReasonCode, MqttError, PubrecReasonCode,
+    ConnectReasonCode, ConnectReturnCode, DisconnectReasonCode, MqttError, PubackReasonCode,
+    PubrecReasonCode,
 };
 
 /// MQTT protocol maximum packet size limit
 /// 1 (fixed header) + 4 (remaining length) + 128^4 (maximum remaining length value)
 const MQTT_PACKET_SIZE_NO_LIMIT: u32 = 1 + 4 + 128 * 128 * 128 * 128;
 
+/// Maximum value of remaining length (maximum value of Variable Byte Integer)
+///
+/// 128^4 - 1, the same for MQTT v3.1.1 and v5.0.
+pub const MQTT_MAX_REMAINING_LENGTH: u32 = 268_435_455;
+
+/// Maximum total size of an MQTT packet
+///
+/// 1 (fixed header) + 4 (remaining length) + `MQTT_MAX_REMAINING_LENGTH`
+pub const MQTT_MAX_PACKET_SIZE: u32 = 1 + 4 + MQTT_MAX_REMAINING_LENGTH;
+
 /// Calculate total packet size from remaining length
 ///
 /// The total packet size consists of:
@@ -94,6 +113,61 @@ fn remaining_length_to_total_size(remaining_length: u32) -> u32 {
 /// It uses `u16` for packet IDs, which is the standard MQTT packet ID type.
 pub type Event = GenericEvent<u16>;
 
+/// Snapshot of the usage of the negotiated limits
+///
+/// Returned by `GenericConnection::limit_status()` to diagnose why sends are failing
+/// or about to fail. A maximum of `None` means the limit is not set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitStatus {
+    /// Number of sent QoS 1 and QoS 2 PUBLISH packets whose flow is not completed yet
+    pub publish_send_inflight: usize,
+    /// Receive Maximum of the peer
+    pub publish_send_max: Option<u16>,
+    /// Number of received QoS 1 and QoS 2 PUBLISH packets not yet acknowledged
+    pub publish_recv_inflight: usize,
+    /// Receive Maximum sent to the peer
+    pub publish_recv_max: Option<u16>,
+    /// Size in bytes of the largest stored packet waiting to be resent
+    pub max_stored_packet_size: usize,
+    /// Maximum Packet Size of the peer
+    pub maximum_packet_size_send: u32,
+    /// Number of topic aliases registered for sending
+    pub topic_alias_send_used: usize,
+    /// Topic Alias Maximum of the peer
+    pub topic_alias_send_max: Option<u16>,
+    /// Number of topic aliases registered by the peer
+    pub topic_alias_recv_used: usize,
+    /// Topic Alias Maximum sent to the peer
+    pub topic_alias_recv_max: Option<u16>,
+    /// Number of handled QoS 2 PUBLISH packet IDs kept for duplicate detection
+    pub qos2_handled: usize,
+}
+
+impl LimitStatus {
+    /// Returns `true` if no more QoS 1 or QoS 2 PUBLISH can be sent until one is acknowledged
+    pub fn is_publish_send_saturated(&self) -> bool {
+        self.publish_send_max
+            .is_some_and(|max| self.publish_send_inflight >= max as usize)
+    }
+
+    /// Returns `true` if the peer has used up the Receive Maximum sent to it
+    pub fn is_publish_recv_saturated(&self) -> bool {
+        self.publish_recv_max
+            .is_some_and(|max| self.publish_recv_inflight >= max as usize)
+    }
+
+    /// Returns `true` if registering a new topic alias for sending evicts an existing one
+    pub fn is_topic_alias_send_saturated(&self) -> bool {
+        self.topic_alias_send_max
+            .is_some_and(|max| self.topic_alias_send_used >= max as usize)
+    }
+
+    /// Returns `true` if a stored packet cannot be resent within the peer's Maximum Packet Size
+    pub fn is_stored_packet_too_large(&self) -> bool {
+        self.max_stored_packet_size > self.maximum_packet_size_send as usize
+    }
+}
+
 /// Generic MQTT Connection - Core Sans-I/O MQTT protocol implementation
 ///
 /// This struct represents the core MQTT protocol logic in a Sans-I/O (synchronous I/O-independent) design.
@@ -146,10 +220,13 @@ where
     protocol_version: Version,
 
     pid_man: PacketIdManager<PacketIdType>,
-    pid_suback: HashSet<PacketIdType>,
+    // Packet IDs waiting for SUBACK with the number of entries in the SUBSCRIBE
+    pid_suback: HashMap<PacketIdType, usize>,
     pid_unsuback: HashSet<PacketIdType>,
     pid_puback: HashSet<PacketIdType>,
     pid_pubrec: HashSet<PacketIdType>,
+    // QoS 2 packet IDs whose PUBREC has been received and PUBREL has not been sent yet
+    pid_pubrel: HashSet<PacketIdType>,
     pid_pubcomp: HashSet<PacketIdType>,
 
     need_store: bool,
@@ -159,15 +236,47 @@ where
     offline_publish: bool,
     auto_pub_response: bool,
     auto_ping_response: bool,
+    // Reject v5.0 CONNECT with an empty client identifier
+    require_client_id: bool,
+    // Reject CONNECT with a client identifier longer than this many bytes
+    max_client_id_length: Option<usize>,
+    // Minimum non-zero keep-alive accepted from CONNECT
+    min_keep_alive: Option<u16>,
+    // Reject CONNECT below min_keep_alive instead of raising it by ServerKeepAlive
+    reject_below_min_keep_alive: bool,
+    // ServerKeepAlive to be sent in CONNACK when the requested keep-alive was raised
+    server_keep_alive: Option<u16>,
+    // Accept SUBACK/UNSUBACK with an unknown packet identifier instead of closing
+    ignore_unknown_suback: bool,
+    // Maximum number of packets processed by one recv() call
+    max_packets_per_recv: Option<usize>,
+    // Reject received PUBLISH to $-prefixed topics (server)
+    reject_dollar_publish: bool,
+    // Notify received packets as NotifyPacketReceivedEx
+    notify_auto_responded: bool,
+    // Emit NotifyErrorContext after NotifyError caused by a received packet
+    error_context: bool,
+    // Emit NotifyStoreEmpty when a received response empties the store
+    notify_store_empty: bool,
+    // Emit NotifyWarning for tolerated protocol irregularities
+    notify_warning: bool,
+    // Whether the received v5.0 CONNECT contained Authentication Method (server)
+    auth_method_received: bool,
+    // Reason String attached to automatically generated v5.0 DISCONNECT
+    auto_disconnect_reason_string: Option<String>,
 
     // Auto map topic alias for sending
     auto_map_topic_alias_send: bool,
     // Auto replace topic alias for sending
     auto_replace_topic_alias_send: bool,
+    // Reject received PUBLISH that remaps an alias to a different topic
+    strict_topic_alias_remap: bool,
     // Topic alias management for receiving
     topic_alias_recv: Option<TopicAliasRecv>,
     // Topic alias management for sending
     topic_alias_send: Option<TopicAliasSend>,
+    // Number of topic aliases overwritten by LRU on auto mapping
+    topic_alias_eviction_count: u64,
 
     publish_send_max: Option<u16>,
     // Maximum number of concurrent PUBLISH packets for receiving
@@ -178,6 +287,17 @@ where
 
     // Set of received PUBLISH packets (for flow control)
     publish_recv: HashSet<PacketIdType>,
+    // Received QoS 1/2 PUBLISH packets waiting for an application-sent PUBACK/PUBREC
+    publish_ack_pending: HashMap<PacketIdType, Qos>,
+
+    // Session Expiry Interval negotiated by v5.0 CONNECT and CONNACK
+    session_expiry_interval: Option<u32>,
+
+    // Server capabilities advertised by v5.0 CONNACK
+    retain_available: bool,
+    wildcard_subscription_available: bool,
+    shared_subscription_available: bool,
+    subscription_identifier_available: bool,
 
     // Maximum packet size for sending
     maximum_packet_size_send: u32,
@@ -196,18 +316,46 @@ where
     pingreq_recv_timeout_ms: u64,
     // PINGRESP receive timeout in milliseconds
     pingresp_recv_timeout_ms: u64,
+    // CONNECT to CONNACK timeout in milliseconds
+    connect_timeout_ms: Option<u64>,
+    // QoS2 handled packet ID time to live in milliseconds
+    qos2_handled_ttl_ms: Option<u64>,
+    // Override whether notify_closed() clears handled QoS2 packet IDs
+    clear_qos2_on_close: Option<bool>,
 
     // QoS2 PUBLISH packet handling state (for duplicate detection)
     qos2_publish_handled: HashSet<PacketIdType>,
+    // QoS2 handled packet IDs that have survived one expiry period
+    qos2_publish_handled_aged: HashSet<PacketIdType>,
 
     // Timer state flags
     pingreq_send_set: bool,
     pingreq_recv_set: bool,
     pingresp_recv_set: bool,
+    connect_timeout_set: bool,
+    qos2_handled_expiry_set: bool,
 
     packet_builder: PacketBuilder,
     // Client/Server mode flag
     is_client: bool,
+    // Whether is_client has been determined by CONNECT send/recv
+    role_determined: bool,
+
+    // Observer called for every sent and received packet
+    packet_observer: Option<fn(Direction, &GenericPacket<PacketIdType>)>,
+    // Transform applied to packets sent by send() and checked_send()
+    outgoing_transform: Option<fn(GenericPacket<PacketIdType>) -> GenericPacket<PacketIdType>>,
+    // Serialized bytes of the most recently sent packet, kept only when capturing
+    capture_last_sent: bool,
+    last_sent_bytes: Option<Vec<u8>>,
+    // Callback called once per connection when it is disconnected
+    on_disconnected: Option<fn(DisconnectCause)>,
+    // Whether on_disconnected has been called since the connection was started
+    disconnected_notified: bool,
+
+    // QoS and retain used by publish_default()
+    default_publish_qos: Qos,
+    default_publish_retain: bool,
 }
 
 /// Type alias for Connection with u16 packet ID (standard case)
@@ -256,24 +404,47 @@ where
             _marker: PhantomData,
             protocol_version: version,
             pid_man: PacketIdManager::new(),
-            pid_suback: HashSet::default(),
+            pid_suback: HashMap::default(),
             pid_unsuback: HashSet::default(),
             pid_puback: HashSet::default(),
             pid_pubrec: HashSet::default(),
+            pid_pubrel: HashSet::default(),
             pid_pubcomp: HashSet::default(),
             need_store: false,
             store: GenericStore::new(),
             offline_publish: false,
             auto_pub_response: false,
             auto_ping_response: false,
+            require_client_id: false,
+            max_client_id_length: None,
+            min_keep_alive: None,
+            reject_below_min_keep_alive: false,
+            server_keep_alive: None,
+            ignore_unknown_suback: false,
+            max_packets_per_recv: None,
+            reject_dollar_publish: false,
+            notify_auto_responded: false,
+            error_context: false,
+            notify_store_empty: false,
+            notify_warning: false,
+            auth_method_received: false,
+            auto_disconnect_reason_string: None,
             auto_map_topic_alias_send: false,
             auto_replace_topic_alias_send: false,
+            strict_topic_alias_remap: false,
             topic_alias_recv: None,
             topic_alias_send: None,
+            topic_alias_eviction_count: 0,
             publish_send_max: None,
             publish_recv_max: None,
             publish_send_count: 0,
             publish_recv: HashSet::default(),
+            publish_ack_pending: HashMap::default(),
+            session_expiry_interval: None,
+            retain_available: true,
+            wildcard_subscription_available: true,
+            shared_subscription_available: true,
+            subscription_identifier_available: true,
             maximum_packet_size_send: MQTT_PACKET_SIZE_NO_LIMIT,
             maximum_packet_size_recv: MQTT_PACKET_SIZE_NO_LIMIT,
             status: ConnectionStatus::Disconnected,
@@ -282,12 +453,27 @@ where
             pingreq_server_keep_alive_ms: None,
             pingreq_recv_timeout_ms: 0,
             pingresp_recv_timeout_ms: 0,
+            connect_timeout_ms: None,
+            qos2_handled_ttl_ms: None,
+            clear_qos2_on_close: None,
             qos2_publish_handled: HashSet::default(),
+            qos2_publish_handled_aged: HashSet::default(),
             pingreq_send_set: false,
             pingreq_recv_set: false,
             pingresp_recv_set: false,
+            connect_timeout_set: false,
+            qos2_handled_expiry_set: false,
             packet_builder: PacketBuilder::new(),
             is_client: false,
+            role_determined: false,
+            packet_observer: None,
+            outgoing_transform: None,
+            capture_last_sent: false,
+            last_sent_bytes: None,
+            on_disconnected: None,
+            disconnected_notified: true,
+            default_publish_qos: Qos::AtMostOnce,
+            default_publish_retain: false,
         }
     }
 
@@ -336,7 +522,9 @@ where
         T: Sendable<Role, PacketIdType>,
     {
         // dispatch concrete packet or generic packet
-        packet.dispatch_send(self)
+        let events = packet.dispatch_send(self);
+        self.observe_packets(&events);
+        events
     }
 
     /// Send MQTT packet with runtime role validation
@@ -384,13 +572,309 @@ where
     /// }
     /// ```
     pub fn send(&mut self, packet: GenericPacket<PacketIdType>) -> Vec<GenericEvent<PacketIdType>> {
+        let events = self.process_send_transformed(packet);
+        self.observe_packets(&events);
+        events
+    }
+
+    /// Send PUBLISH packet using the default QoS and retain flag
+    ///
+    /// Builds a PUBLISH packet for the connection's protocol version with the QoS set by
+    /// `set_default_publish_qos()` and the retain flag set by `set_default_publish_retain()`,
+    /// and sends it. If the QoS is 1 or 2, a packet ID is acquired automatically.
+    ///
+    /// # Parameters
+    ///
+    /// * `topic_name` - The topic name to publish to
+    /// * `payload` - The application message
+    ///
+    /// # Returns
+    ///
+    /// A vector of events that the application must process. If the packet ID cannot be
+    /// acquired, the packet cannot be built, or the packet exceeds the peer's Maximum Packet
+    /// Size, a `NotifyError` event is returned. An acquired packet ID is released in that case
+    /// and reported by a `NotifyPacketIdReleased` event.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// connection.set_default_publish_qos(mqtt::packet::Qos::AtLeastOnce);
+    /// let events = connection.publish_default("sensors/temperature", "23.5");
+    /// ```
+    pub fn publish_default(
+        &mut self,
+        topic_name: &str,
+        payload: impl IntoPayload,
+    ) -> Vec<GenericEvent<PacketIdType>>
+    where
+        PacketIdType: IntoPacketId<PacketIdType>,
+    {
+        let qos = self.default_publish_qos;
+        let retain = self.default_publish_retain;
+        let packet_id = if qos == Qos::AtMostOnce {
+            None
+        } else {
+            match self.pid_man.acquire_unique_id() {
+                Ok(packet_id) => Some(packet_id),
+                Err(e) => return vec![GenericEvent::NotifyError(e)],
+            }
+        };
+
+        let packet: Result<GenericPacket<PacketIdType>, MqttError> = match self.protocol_version {
+            Version::V3_1_1 => v3_1_1::GenericPublish::<PacketIdType>::builder()
+                .topic_name(topic_name)
+                .map(|builder| {
+                    let builder = builder.qos(qos).retain(retain).payload(payload);
+                    match packet_id {
+                        Some(packet_id) => builder.packet_id(packet_id),
+                        None => builder,
+                    }
+                })
+                .and_then(|builder| builder.build())
+                .map(|packet| packet.into()),
+            Version::V5_0 => v5_0::GenericPublish::<PacketIdType>::builder()
+                .topic_name(topic_name)
+                .map(|builder| {
+                    let builder = builder.qos(qos).retain(retain).payload(payload);
+                    match packet_id {
+                        Some(packet_id) => builder.packet_id(packet_id),
+                        None => builder,
+                    }
+                })
+                .and_then(|builder| builder.build())
+                .map(|packet| packet.into()),
+            Version::Undetermined => Err(MqttError::PacketNotAllowedToSend),
+        };
+        // Checked here so that the acquired packet ID is released below
+        let packet = packet.and_then(|packet| {
+            if self.validate_maximum_packet_size_send(packet.size()) {
+                Ok(packet)
+            } else {
+                Err(MqttError::PacketTooLarge)
+            }
+        });
+
+        match packet {
+            Ok(packet) => self.send(packet),
+            Err(e) => {
+                let mut events = Vec::new();
+                if let Some(packet_id) = packet_id {
+                    self.pid_man.release_id(packet_id);
+                    events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                }
+                events.push(GenericEvent::NotifyError(e));
+                events
+            }
+        }
+    }
+
+    /// Enumerate the reasons a packet would be rejected by `send()` right now
+    ///
+    /// Unlike `send()`, which stops at the first failed check, this method evaluates
+    /// every check and returns all applicable reasons. The connection state is not
+    /// modified.
+    ///
+    /// # Parameters
+    ///
+    /// * `packet` - The MQTT packet to examine
+    ///
+    /// # Returns
+    ///
+    /// A vector of errors, empty if none of the checks fail:
+    /// - `VersionMismatch` if the packet version differs from the connection version
+    /// - `PacketTooLarge` if the packet exceeds the peer's Maximum Packet Size
+    /// - `PacketNotAllowedToSend` if the connection role or state does not allow the
+    ///   packet, or the TopicAlias of a v5.0 PUBLISH is invalid
+    /// - `PacketIdentifierInvalid` if the packet identifier has not been acquired or registered
+    /// - `PubrecNotReceived` if a PUBREL is not for a QoS 2 flow whose PUBREC has been received
+    /// - `ReceiveMaximumExceeded` if a QoS 1/2 PUBLISH would exceed the send window
+    ///
+    /// These are the same errors that `send()` reports, and each appears at most once.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// for reason in connection.send_rejections(&packet) {
+    ///     println!("cannot send: {reason}");
+    /// }
+    /// ```
+    pub fn send_rejections(&self, packet: &GenericPacket<PacketIdType>) -> Vec<MqttError> {
+        let mut reasons = Vec::new();
+        let mut reject = |e: MqttError| {
+            if !reasons.contains(&e) {
+                reasons.push(e);
+            }
+        };
+
+        if self.protocol_version != packet.protocol_version() {
+            reject(MqttError::VersionMismatch);
+        }
+        if !Self::is_sendable_by_role(packet) {
+            reject(MqttError::PacketNotAllowedToSend);
+        }
+        if !self.validate_maximum_packet_size_send(packet.size()) {
+            reject(MqttError::PacketTooLarge);
+        }
+        if !self.is_sendable_in_state(packet) {
+            reject(MqttError::PacketNotAllowedToSend);
+        }
+
+        let packet_id = match packet {
+            GenericPacket::V3_1_1Publish(p) => p.packet_id(),
+            GenericPacket::V5_0Publish(p) => p.packet_id(),
+            GenericPacket::V3_1_1Pubrel(p) => Some(p.packet_id()),
+            GenericPacket::V5_0Pubrel(p) => Some(p.packet_id()),
+            GenericPacket::V3_1_1Subscribe(p) => Some(p.packet_id()),
+            GenericPacket::V5_0Subscribe(p) => Some(p.packet_id()),
+            GenericPacket::V3_1_1Unsubscribe(p) => Some(p.packet_id()),
+            GenericPacket::V5_0Unsubscribe(p) => Some(p.packet_id()),
+            _ => None,
+        };
+        if let Some(packet_id) = packet_id {
+            if !self.pid_man.is_used_id(packet_id) {
+                reject(MqttError::PacketIdentifierInvalid);
+            }
+            if matches!(
+                packet,
+                GenericPacket::V3_1_1Pubrel(_) | GenericPacket::V5_0Pubrel(_)
+            ) && !self.pid_pubrel.contains(&packet_id)
+            {
+                reject(MqttError::PubrecNotReceived);
+            }
+        }
+
+        if let GenericPacket::V5_0Publish(p) = packet {
+            if !self.is_topic_alias_sendable(p) {
+                reject(MqttError::PacketNotAllowedToSend);
+            }
+        }
+        if packet_id.is_some()
+            && matches!(
+                packet,
+                GenericPacket::V3_1_1Publish(_) | GenericPacket::V5_0Publish(_)
+            )
+            && self.is_publish_send_window_full()
+        {
+            reject(MqttError::ReceiveMaximumExceeded);
+        }
+
+        reasons
+    }
+
+    // Whether the connection role can send the packet type
+    fn is_sendable_by_role(packet: &GenericPacket<PacketIdType>) -> bool {
         use core::any::TypeId;
 
         let role_id = TypeId::of::<Role>();
-        let client_id = TypeId::of::<role::Client>();
-        let server_id = TypeId::of::<role::Server>();
-        let any_id = TypeId::of::<role::Any>();
+        match packet {
+            // CONNECT, SUBSCRIBE, UNSUBSCRIBE, PINGREQ, DISCONNECT(v3.1.1) - Client/Any can send
+            GenericPacket::V3_1_1Connect(_)
+            | GenericPacket::V5_0Connect(_)
+            | GenericPacket::V3_1_1Subscribe(_)
+            | GenericPacket::V5_0Subscribe(_)
+            | GenericPacket::V3_1_1Unsubscribe(_)
+            | GenericPacket::V5_0Unsubscribe(_)
+            | GenericPacket::V3_1_1Pingreq(_)
+            | GenericPacket::V5_0Pingreq(_)
+            | GenericPacket::V3_1_1Disconnect(_) => role_id != TypeId::of::<role::Server>(),
+            // CONNACK, SUBACK, UNSUBACK, PINGRESP - Server/Any can send
+            GenericPacket::V3_1_1Connack(_)
+            | GenericPacket::V5_0Connack(_)
+            | GenericPacket::V3_1_1Suback(_)
+            | GenericPacket::V5_0Suback(_)
+            | GenericPacket::V3_1_1Unsuback(_)
+            | GenericPacket::V5_0Unsuback(_)
+            | GenericPacket::V3_1_1Pingresp(_)
+            | GenericPacket::V5_0Pingresp(_) => role_id != TypeId::of::<role::Client>(),
+            // PUBLISH, PUBACK, PUBREC, PUBREL, PUBCOMP, DISCONNECT(v5.0), AUTH - Any role can send
+            _ => true,
+        }
+    }
+
+    // Whether the connection state allows sending the packet
+    fn is_sendable_in_state(&self, packet: &GenericPacket<PacketIdType>) -> bool {
+        match packet {
+            GenericPacket::V3_1_1Connect(_) | GenericPacket::V5_0Connect(_) => {
+                self.status == ConnectionStatus::Disconnected
+            }
+            GenericPacket::V3_1_1Connack(_) | GenericPacket::V5_0Connack(_) => {
+                self.status == ConnectionStatus::Connecting
+            }
+            GenericPacket::V3_1_1Publish(p) => self.is_publish_sendable_in_state(p.qos()),
+            GenericPacket::V5_0Publish(p) => self.is_publish_sendable_in_state(p.qos()),
+            GenericPacket::V3_1_1Pubrel(_) | GenericPacket::V5_0Pubrel(_) => {
+                self.is_pubrel_sendable_in_state()
+            }
+            GenericPacket::V5_0Auth(_) => self.is_auth_sendable_in_state(),
+            _ => self.status == ConnectionStatus::Connected,
+        }
+    }
+
+    // QoS 1/2 PUBLISH can be stored while not connected
+    fn is_publish_sendable_in_state(&self, qos: Qos) -> bool {
+        self.status == ConnectionStatus::Connected
+            || (qos != Qos::AtMostOnce && (self.need_store || self.offline_publish))
+    }
+
+    // PUBREL can be stored while not connected
+    fn is_pubrel_sendable_in_state(&self) -> bool {
+        self.status == ConnectionStatus::Connected || self.need_store
+    }
+
+    // A server can send AUTH only if CONNECT had Authentication Method
+    fn is_auth_sendable_in_state(&self) -> bool {
+        self.status != ConnectionStatus::Disconnected
+            && (self.is_client || self.auth_method_received)
+    }
+
+    // Whether the TopicAlias of the PUBLISH can be sent, without updating the LRU
+    fn is_topic_alias_sendable(&self, packet: &v5_0::GenericPublish<PacketIdType>) -> bool {
+        match Self::get_topic_alias_from_props(packet.props()) {
+            Some(ta) if packet.topic_name().is_empty() => {
+                self.validate_topic_alias_range(ta)
+                    && self
+                        .topic_alias_send
+                        .as_ref()
+                        .is_some_and(|tas| tas.peek(ta).is_some())
+            }
+            Some(ta) => self.validate_topic_alias_range(ta),
+            None => !packet.topic_name().is_empty(),
+        }
+    }
+
+    // Whether QoS 1/2 PUBLISH would exceed the peer's Receive Maximum
+    fn is_publish_send_window_full(&self) -> bool {
+        self.publish_send_max
+            .is_some_and(|max| self.publish_send_count >= max)
+    }
+
+    // Whether concrete packets must be routed through process_send_transformed()
+    pub(crate) fn has_outgoing_transform(&self) -> bool {
+        self.outgoing_transform.is_some()
+    }
+
+    // Apply the outgoing transform before validation and storing,
+    // so the stored copy and the size check match what is sent
+    pub(crate) fn process_send_transformed(
+        &mut self,
+        packet: GenericPacket<PacketIdType>,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        let packet = match self.outgoing_transform {
+            Some(transform) => transform(packet),
+            None => packet,
+        };
+        self.process_send(packet)
+    }
 
+    // Runtime role validated send without notifying the packet observer
+    pub(crate) fn process_send(
+        &mut self,
+        packet: GenericPacket<PacketIdType>,
+    ) -> Vec<GenericEvent<PacketIdType>> {
         // Check version compatibility between connection and packet
         let packet_version = packet.protocol_version();
 
@@ -399,41 +883,17 @@ where
             return vec![GenericEvent::NotifyError(MqttError::VersionMismatch)];
         }
 
+        if !Self::is_sendable_by_role(&packet) {
+            return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
+        }
+
         match packet {
-            // CONNECT - Client/Any can send
-            GenericPacket::V3_1_1Connect(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v3_1_1_connect(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Connect(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v5_0_connect(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // CONNACK - Server/Any can send
-            GenericPacket::V3_1_1Connack(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v3_1_1_connack(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Connack(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v5_0_connack(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // PUBLISH - Any role can send
+            GenericPacket::V3_1_1Connect(p) => self.process_send_v3_1_1_connect(p),
+            GenericPacket::V5_0Connect(p) => self.process_send_v5_0_connect(p),
+            GenericPacket::V3_1_1Connack(p) => self.process_send_v3_1_1_connack(p),
+            GenericPacket::V5_0Connack(p) => self.process_send_v5_0_connack(p),
             GenericPacket::V3_1_1Publish(p) => self.process_send_v3_1_1_publish(p),
             GenericPacket::V5_0Publish(p) => self.process_send_v5_0_publish(p),
-            // PUBACK/PUBREC/PUBREL/PUBCOMP - Any role can send
             GenericPacket::V3_1_1Puback(p) => self.process_send_v3_1_1_puback(p),
             GenericPacket::V5_0Puback(p) => self.process_send_v5_0_puback(p),
             GenericPacket::V3_1_1Pubrec(p) => self.process_send_v3_1_1_pubrec(p),
@@ -442,105 +902,20 @@ where
             GenericPacket::V5_0Pubrel(p) => self.process_send_v5_0_pubrel(p),
             GenericPacket::V3_1_1Pubcomp(p) => self.process_send_v3_1_1_pubcomp(p),
             GenericPacket::V5_0Pubcomp(p) => self.process_send_v5_0_pubcomp(p),
-            // SUBSCRIBE/UNSUBSCRIBE - Client/Any can send
-            GenericPacket::V3_1_1Subscribe(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v3_1_1_subscribe(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Subscribe(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v5_0_subscribe(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V3_1_1Unsubscribe(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v3_1_1_unsubscribe(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Unsubscribe(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v5_0_unsubscribe(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // SUBACK/UNSUBACK - Server/Any can send
-            GenericPacket::V3_1_1Suback(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v3_1_1_suback(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Suback(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v5_0_suback(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V3_1_1Unsuback(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v3_1_1_unsuback(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Unsuback(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v5_0_unsuback(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // PINGREQ - Client/Any can send
-            GenericPacket::V3_1_1Pingreq(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v3_1_1_pingreq(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Pingreq(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v5_0_pingreq(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // PINGRESP - Server/Any can send
-            GenericPacket::V3_1_1Pingresp(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v3_1_1_pingresp(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            GenericPacket::V5_0Pingresp(p) => {
-                if role_id == server_id || role_id == any_id {
-                    self.process_send_v5_0_pingresp(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // DISCONNECT(v3.1.1) - Client/Any role can send
-            GenericPacket::V3_1_1Disconnect(p) => {
-                if role_id == client_id || role_id == any_id {
-                    self.process_send_v3_1_1_disconnect(p)
-                } else {
-                    vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)]
-                }
-            }
-            // DISCONNECT(v5.0) - Any role can send
+            GenericPacket::V3_1_1Subscribe(p) => self.process_send_v3_1_1_subscribe(p),
+            GenericPacket::V5_0Subscribe(p) => self.process_send_v5_0_subscribe(p),
+            GenericPacket::V3_1_1Suback(p) => self.process_send_v3_1_1_suback(p),
+            GenericPacket::V5_0Suback(p) => self.process_send_v5_0_suback(p),
+            GenericPacket::V3_1_1Unsubscribe(p) => self.process_send_v3_1_1_unsubscribe(p),
+            GenericPacket::V5_0Unsubscribe(p) => self.process_send_v5_0_unsubscribe(p),
+            GenericPacket::V3_1_1Unsuback(p) => self.process_send_v3_1_1_unsuback(p),
+            GenericPacket::V5_0Unsuback(p) => self.process_send_v5_0_unsuback(p),
+            GenericPacket::V3_1_1Pingreq(p) => self.process_send_v3_1_1_pingreq(p),
+            GenericPacket::V5_0Pingreq(p) => self.process_send_v5_0_pingreq(p),
+            GenericPacket::V3_1_1Pingresp(p) => self.process_send_v3_1_1_pingresp(p),
+            GenericPacket::V5_0Pingresp(p) => self.process_send_v5_0_pingresp(p),
+            GenericPacket::V3_1_1Disconnect(p) => self.process_send_v3_1_1_disconnect(p),
             GenericPacket::V5_0Disconnect(p) => self.process_send_v5_0_disconnect(p),
-            // AUTH - Any role can send (v5.0 only)
             GenericPacket::V5_0Auth(p) => self.process_send_v5_0_auth(p),
         }
     }
@@ -566,7 +941,11 @@ where
     /// # Behavior
     ///
     /// - Handles partial packets (data will be buffered until complete)
-    /// - Processes multiple complete packets in sequence
+    /// - Processes multiple complete packets in sequence, up to the limit set by
+    ///   `set_max_packets_per_recv()`. The remaining data is left in the cursor.
+    /// - Stops processing after a packet that causes the connection to be closed, or
+    ///   that is processed while the connection is disconnected. The remaining data is
+    ///   left in the cursor.
     /// - Validates packet structure and protocol compliance
     /// - Updates internal connection state based on received packets
     /// - Generates appropriate response events (ACKs, etc.)
@@ -595,19 +974,49 @@ where
     /// ```
     pub fn recv(&mut self, data: &mut Cursor<&[u8]>) -> Vec<GenericEvent<PacketIdType>> {
         let mut events = Vec::new();
+        let mut processed = 0;
 
-        match self.packet_builder.feed(data) {
-            PacketBuildResult::Complete(raw_packet) => {
-                events.extend(self.process_recv_packet(raw_packet));
+        loop {
+            if let Some(max) = self.max_packets_per_recv {
+                if processed >= max {
+                    break;
+                }
             }
-            PacketBuildResult::Incomplete => {}
-            PacketBuildResult::Error(e) => {
-                self.cancel_timers(&mut events);
-                events.push(GenericEvent::RequestClose);
-                events.push(GenericEvent::NotifyError(e));
+            match self.packet_builder.feed(data) {
+                PacketBuildResult::Complete(raw_packet) => {
+                    processed += 1;
+                    let context = self
+                        .error_context
+                        .then(|| Self::recv_packet_context(&raw_packet));
+                    let mut packet_events = self.process_recv_packet(raw_packet);
+                    if self.notify_auto_responded {
+                        packet_events = Self::mark_auto_responded(packet_events);
+                    }
+                    if let Some((packet_type, packet_id)) = context {
+                        packet_events =
+                            Self::add_error_context(packet_events, packet_type, packet_id);
+                    }
+                    let closed = self.status == ConnectionStatus::Disconnected
+                        || packet_events
+                            .iter()
+                            .any(|e| matches!(e, GenericEvent::RequestClose));
+                    events.extend(packet_events);
+                    if closed {
+                        break;
+                    }
+                }
+                PacketBuildResult::Incomplete => break,
+                PacketBuildResult::Error(e) => {
+                    self.notify_disconnected(DisconnectCause::ProtocolError);
+                    self.cancel_timers(&mut events);
+                    events.push(GenericEvent::RequestClose);
+                    events.push(GenericEvent::NotifyError(e));
+                    break;
+                }
             }
         }
 
+        self.observe_packets(&events);
         events
     }
 
@@ -635,8 +1044,8 @@ where
                 // Reset timer flag
                 self.pingreq_send_set = false;
 
-                // Send PINGREQ if connected
-                if self.status == ConnectionStatus::Connected {
+                // Send PINGREQ if connected as a client
+                if self.status == ConnectionStatus::Connected && self.is_client {
                     match self.protocol_version {
                         Version::V3_1_1 => {
                             if let Ok(pingreq) = v3_1_1::Pingreq::builder().build() {
@@ -661,17 +1070,18 @@ where
                 match self.protocol_version {
                     Version::V3_1_1 => {
                         // V3.1.1: Close connection
+                        self.notify_disconnected(DisconnectCause::KeepAliveTimeout);
                         events.push(GenericEvent::RequestClose);
                     }
                     Version::V5_0 => {
                         // V5.0: Send DISCONNECT with keep_alive_timeout if connected
                         if self.status == ConnectionStatus::Connected {
-                            if let Ok(disconnect) = v5_0::Disconnect::builder()
-                                .reason_code(DisconnectReasonCode::KeepAliveTimeout)
-                                .build()
-                            {
-                                events.extend(self.process_send_v5_0_disconnect(disconnect));
-                            }
+                            let disconnect =
+                                self.build_auto_disconnect(DisconnectReasonCode::KeepAliveTimeout);
+                            events.extend(self.send_v5_0_disconnect(
+                                disconnect,
+                                DisconnectCause::KeepAliveTimeout,
+                            ));
                         }
                     }
                     Version::Undetermined => {
@@ -686,17 +1096,18 @@ where
                 match self.protocol_version {
                     Version::V3_1_1 => {
                         // V3.1.1: Close connection
+                        self.notify_disconnected(DisconnectCause::KeepAliveTimeout);
                         events.push(GenericEvent::RequestClose);
                     }
                     Version::V5_0 => {
                         // V5.0: Send DISCONNECT with keep_alive_timeout if connected
                         if self.status == ConnectionStatus::Connected {
-                            if let Ok(disconnect) = v5_0::Disconnect::builder()
-                                .reason_code(DisconnectReasonCode::KeepAliveTimeout)
-                                .build()
-                            {
-                                events.extend(self.process_send_v5_0_disconnect(disconnect));
-                            }
+                            let disconnect =
+                                self.build_auto_disconnect(DisconnectReasonCode::KeepAliveTimeout);
+                            events.extend(self.send_v5_0_disconnect(
+                                disconnect,
+                                DisconnectCause::KeepAliveTimeout,
+                            ));
                         }
                     }
                     Version::Undetermined => {
@@ -704,8 +1115,31 @@ where
                     }
                 }
             }
+            TimerKind::ConnectTimeout => {
+                // Reset timer flag
+                self.connect_timeout_set = false;
+
+                // Close connection if the handshake has not been completed
+                if self.status == ConnectionStatus::Connecting {
+                    self.set_disconnected(DisconnectCause::ConnectTimeout);
+                    self.cancel_timers(&mut events);
+                    events.push(GenericEvent::RequestClose);
+                }
+            }
+            TimerKind::Qos2HandledExpiry => {
+                // Reset timer flag
+                self.qos2_handled_expiry_set = false;
+
+                // Evict IDs that were already handled at the previous expiry
+                for packet_id in self.qos2_publish_handled_aged.drain() {
+                    self.qos2_publish_handled.remove(&packet_id);
+                }
+                self.qos2_publish_handled_aged = self.qos2_publish_handled.clone();
+                self.arm_qos2_handled_expiry(&mut events);
+            }
         }
 
+        self.observe_packets(&events);
         events
     }
 
@@ -729,14 +1163,14 @@ where
         self.maximum_packet_size_recv = MQTT_PACKET_SIZE_NO_LIMIT;
 
         // Set status to disconnected
-        self.status = ConnectionStatus::Disconnected;
+        self.set_disconnected(DisconnectCause::Closed);
 
         // Clear topic alias management
         self.topic_alias_send = None;
         self.topic_alias_recv = None;
 
         // Release packet IDs for SUBACK
-        for packet_id in self.pid_suback.drain() {
+        for (packet_id, _) in self.pid_suback.drain() {
             if self.pid_man.is_used_id(packet_id) {
                 self.pid_man.release_id(packet_id);
                 events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
@@ -751,10 +1185,14 @@ where
             }
         }
 
-        // If not storing session state, clear QoS2 states and release publish-related packet IDs
-        if !self.need_store {
+        // If not storing session state, clear QoS2 states unless overridden
+        if self.clear_qos2_on_close.unwrap_or(!self.need_store) {
             self.qos2_publish_handled.clear();
+            self.qos2_publish_handled_aged.clear();
+        }
 
+        // If not storing session state, release publish-related packet IDs
+        if !self.need_store {
             // Release packet IDs for PUBACK
             for packet_id in self.pid_puback.drain() {
                 if self.pid_man.is_used_id(packet_id) {
@@ -771,6 +1209,14 @@ where
                 }
             }
 
+            // Release packet IDs for PUBREL to be sent
+            for packet_id in self.pid_pubrel.drain() {
+                if self.pid_man.is_used_id(packet_id) {
+                    self.pid_man.release_id(packet_id);
+                    events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                }
+            }
+
             // Release packet IDs for PUBCOMP
             for packet_id in self.pid_pubcomp.drain() {
                 if self.pid_man.is_used_id(packet_id) {
@@ -830,6 +1276,52 @@ where
         events
     }
 
+    /// Get the armed timer with the shortest duration
+    ///
+    /// Returns the kind and duration in milliseconds of the shortest timer among the
+    /// timers currently requested by `RequestTimerReset` and not cancelled yet. This
+    /// allows a poll-based I/O loop to schedule a single wakeup without tracking every
+    /// timer event. As the connection has no clock, the duration is the one requested
+    /// when the timer was last reset, not the remaining time.
+    ///
+    /// # Returns
+    ///
+    /// `Some((kind, duration_ms))` for the shortest armed timer, or `None` if no timer is armed
+    pub fn next_timer_deadline(&self) -> Option<(TimerKind, u64)> {
+        let timers = [
+            (
+                self.pingreq_send_set,
+                TimerKind::PingreqSend,
+                self.pingreq_send_interval_ms(),
+            ),
+            (
+                self.pingreq_recv_set,
+                TimerKind::PingreqRecv,
+                self.pingreq_recv_timeout_ms,
+            ),
+            (
+                self.pingresp_recv_set,
+                TimerKind::PingrespRecv,
+                self.pingresp_recv_timeout_ms,
+            ),
+            (
+                self.connect_timeout_set,
+                TimerKind::ConnectTimeout,
+                self.connect_timeout_ms.unwrap_or(0),
+            ),
+            (
+                self.qos2_handled_expiry_set,
+                TimerKind::Qos2HandledExpiry,
+                self.qos2_handled_ttl_ms.unwrap_or(0),
+            ),
+        ];
+        timers
+            .into_iter()
+            .filter(|(set, _, _)| *set)
+            .map(|(_, kind, duration_ms)| (kind, duration_ms))
+            .min_by_key(|(_, duration_ms)| *duration_ms)
+    }
+
     /// Get the remaining capacity for sending PUBLISH packets
     ///
     /// Returns the number of additional PUBLISH packets that can be sent
@@ -844,42 +1336,261 @@ where
             .map(|max| max.saturating_sub(self.publish_send_count))
     }
 
-    /// Enable or disable offline publishing
+    /// Get the usage of the negotiated limits
     ///
-    /// When enabled, PUBLISH packets can be sent even when disconnected.
-    /// They will be queued and sent once the connection is established.
+    /// Returns a snapshot of the current usage against each negotiated limit, such as
+    /// in-flight PUBLISH packets against Receive Maximum and registered topic aliases
+    /// against Topic Alias Maximum. This is useful to diagnose why sends fail.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// * `enable` - Whether to enable offline publishing
-    pub fn set_offline_publish(&mut self, enable: bool) {
-        self.offline_publish = enable;
-        if self.offline_publish {
-            self.need_store = true;
+    /// The current `LimitStatus`
+    pub fn limit_status(&self) -> LimitStatus {
+        LimitStatus {
+            publish_send_inflight: self.pid_puback.len()
+                + self.pid_pubrec.len()
+                + self.pid_pubrel.len()
+                + self.pid_pubcomp.len(),
+            publish_send_max: self.publish_send_max,
+            publish_recv_inflight: self.publish_recv.len(),
+            publish_recv_max: self.publish_recv_max,
+            max_stored_packet_size: self.store.max_packet_size(),
+            maximum_packet_size_send: self.maximum_packet_size_send,
+            topic_alias_send_used: self.topic_alias_send_len(),
+            topic_alias_send_max: self.topic_alias_send.as_ref().map(|ta| ta.max()),
+            topic_alias_recv_used: self.topic_alias_recv_len(),
+            topic_alias_recv_max: self.topic_alias_recv.as_ref().map(|ta| ta.max()),
+            qos2_handled: self.qos2_publish_handled.len(),
         }
     }
 
-    /// Enable or disable automatic PUBLISH response generation
+    /// Get the negotiated Session Expiry Interval
     ///
-    /// When enabled, appropriate response packets (PUBACK, PUBREC, PUBREL, and PUBCOMP.)
-    /// are automatically generated for received PUBLISH packets.
+    /// The value is taken from the v5.0 CONNECT and is overridden by the Session Expiry
+    /// Interval property of the CONNACK, if present. An absent property in CONNECT
+    /// means 0, as defined by the specification.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// * `enable` - Whether to enable automatic responses
-    pub fn set_auto_pub_response(&mut self, enable: bool) {
-        self.auto_pub_response = enable;
+    /// The Session Expiry Interval in seconds, or `None` if no v5.0 CONNECT has been
+    /// sent or received
+    pub fn session_expiry_interval(&self) -> Option<u32> {
+        self.session_expiry_interval
     }
 
-    /// Enable or disable automatic PING response generation
+    /// Check whether the server supports retained messages
     ///
-    /// When enabled, PINGRESP packets are automatically sent in response to PINGREQ.
+    /// Reflects the Retain Available property of the last received v5.0 CONNACK.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// * `enable` - Whether to enable automatic PING responses
-    pub fn set_auto_ping_response(&mut self, enable: bool) {
-        self.auto_ping_response = enable;
+    /// `false` if the server advertised that retained messages are not available,
+    /// `true` otherwise, including when the property was absent
+    pub fn supports_retain(&self) -> bool {
+        self.retain_available
+    }
+
+    /// Check whether the server supports wildcard subscriptions
+    ///
+    /// Reflects the Wildcard Subscription Available property of the last received
+    /// v5.0 CONNACK.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the server advertised that wildcard subscriptions are not available,
+    /// `true` otherwise, including when the property was absent
+    pub fn supports_wildcard_subscriptions(&self) -> bool {
+        self.wildcard_subscription_available
+    }
+
+    /// Check whether the server supports shared subscriptions
+    ///
+    /// Reflects the Shared Subscription Available property of the last received
+    /// v5.0 CONNACK.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the server advertised that shared subscriptions are not available,
+    /// `true` otherwise, including when the property was absent
+    pub fn supports_shared_subscriptions(&self) -> bool {
+        self.shared_subscription_available
+    }
+
+    /// Check whether the server supports subscription identifiers
+    ///
+    /// Reflects the Subscription Identifier Available property of the last received
+    /// v5.0 CONNACK.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the server advertised that subscription identifiers are not available,
+    /// `true` otherwise, including when the property was absent
+    pub fn supports_subscription_identifiers(&self) -> bool {
+        self.subscription_identifier_available
+    }
+
+    /// Compute the size a PUBLISH packet would have when sent
+    ///
+    /// Applies the same topic alias substitution that sending the packet would apply
+    /// under the current auto map or auto replace state, without registering any
+    /// alias. A packet that already has a TopicAlias property or an empty topic name
+    /// is sent as is. This can be used to compare the aliased size with the size of
+    /// the packet with its topic name.
+    ///
+    /// # Parameters
+    ///
+    /// * `publish` - The PUBLISH packet to inspect
+    ///
+    /// # Returns
+    ///
+    /// The size of the packet in bytes as it would be sent
+    pub fn publish_encoded_size_with_alias(
+        &self,
+        publish: &v5_0::GenericPublish<PacketIdType>,
+    ) -> usize {
+        if self.status != ConnectionStatus::Connected
+            || publish.topic_name().is_empty()
+            || Self::get_topic_alias_from_props(publish.props()).is_some()
+        {
+            return publish.size();
+        }
+        let Some(ref topic_alias_send) = self.topic_alias_send else {
+            return publish.size();
+        };
+        let found_ta = topic_alias_send.find_by_topic(publish.topic_name());
+        if self.auto_map_topic_alias_send {
+            match found_ta {
+                Some(ta) => publish.clone().remove_topic_add_topic_alias(ta).size(),
+                None => publish
+                    .clone()
+                    .add_topic_alias(topic_alias_send.get_lru_alias())
+                    .size(),
+            }
+        } else if self.auto_replace_topic_alias_send {
+            match found_ta {
+                Some(ta) => publish.clone().remove_topic_add_topic_alias(ta).size(),
+                None => publish.size(),
+            }
+        } else {
+            publish.size()
+        }
+    }
+
+    /// Validate an outgoing SUBSCRIBE packet against the server limits
+    ///
+    /// Checks the packet against the limits advertised by the server in the v5.0
+    /// CONNACK in one call, so that a SUBSCRIBE the server would reject can be
+    /// detected before sending it. The checks are performed in the following order
+    /// and the first violation is returned:
+    /// 1. Maximum Packet Size
+    /// 2. Subscription Identifier Available
+    /// 3. Shared Subscription Available and Wildcard Subscription Available for each entry
+    ///
+    /// # Parameters
+    ///
+    /// * `sub` - The SUBSCRIBE packet to validate
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - The packet satisfies all limits
+    /// * `Err(MqttError::PacketTooLarge)` - The packet exceeds Maximum Packet Size
+    /// * `Err(MqttError::SubscriptionIdentifiersNotSupported)` - Subscription Identifier is not available
+    /// * `Err(MqttError::SharedSubscriptionsNotSupported)` - An entry is a shared subscription
+    ///   but shared subscriptions are not available
+    /// * `Err(MqttError::WildcardSubscriptionsNotSupported)` - An entry contains a wildcard
+    ///   but wildcard subscriptions are not available
+    pub fn validate_subscribe(
+        &self,
+        sub: &v5_0::GenericSubscribe<PacketIdType>,
+    ) -> Result<(), MqttError> {
+        if sub.size() > self.maximum_packet_size_send as usize {
+            return Err(MqttError::PacketTooLarge);
+        }
+        if !self.subscription_identifier_available
+            && sub
+                .props()
+                .iter()
+                .any(|prop| matches!(prop, Property::SubscriptionIdentifier(_)))
+        {
+            return Err(MqttError::SubscriptionIdentifiersNotSupported);
+        }
+        for entry in sub.entries() {
+            let topic_filter = entry.topic_filter();
+            if !self.shared_subscription_available && topic_filter.starts_with("$share/") {
+                return Err(MqttError::SharedSubscriptionsNotSupported);
+            }
+            if !self.wildcard_subscription_available
+                && (topic_filter.contains('+') || topic_filter.contains('#'))
+            {
+                return Err(MqttError::WildcardSubscriptionsNotSupported);
+            }
+        }
+        Ok(())
+    }
+
+    /// Enable or disable offline publishing
+    ///
+    /// When enabled, PUBLISH packets can be sent even when disconnected.
+    /// They will be queued and sent once the connection is established.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to enable offline publishing
+    pub fn set_offline_publish(&mut self, enable: bool) {
+        self.offline_publish = enable;
+        if self.offline_publish {
+            self.need_store = true;
+        }
+    }
+
+    /// Enable or disable automatic PUBLISH response generation
+    ///
+    /// When enabled, appropriate response packets (PUBACK, PUBREC, PUBREL, and PUBCOMP.)
+    /// are automatically generated for received PUBLISH packets.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to enable automatic responses
+    pub fn set_auto_pub_response(&mut self, enable: bool) {
+        self.auto_pub_response = enable;
+    }
+
+    /// Check whether automatic publish responses are effectively active
+    ///
+    /// Automatic PUBACK/PUBREC/PUBREL/PUBCOMP responses are generated only if
+    /// they are enabled by `set_auto_pub_response()` and the connection is connected.
+    ///
+    /// # Returns
+    ///
+    /// `true` if received publish related packets are currently answered automatically
+    pub fn auto_pub_response_active(&self) -> bool {
+        self.auto_pub_response && self.status == ConnectionStatus::Connected
+    }
+
+    /// Enable or disable automatic PING response generation
+    ///
+    /// When enabled, PINGRESP packets are automatically sent in response to PINGREQ.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to enable automatic PING responses
+    pub fn set_auto_ping_response(&mut self, enable: bool) {
+        self.auto_ping_response = enable;
+    }
+
+    /// Check whether automatic PING responses are effectively active
+    ///
+    /// PINGRESP is sent automatically only if it is enabled by `set_auto_ping_response()`,
+    /// the connection acts as a server, and the connection is connected.
+    ///
+    /// # Returns
+    ///
+    /// `true` if received PINGREQ packets are currently answered automatically
+    pub fn auto_ping_response_active(&self) -> bool {
+        (Role::IS_SERVER || Role::IS_ANY)
+            && !self.is_client
+            && self.auto_ping_response
+            && self.status == ConnectionStatus::Connected
     }
 
     /// Enable or disable automatic topic alias mapping for outgoing packets
@@ -897,6 +1608,246 @@ where
         self.auto_map_topic_alias_send = enable;
     }
 
+    /// Get the number of topic aliases evicted by automatic topic alias mapping
+    ///
+    /// When `set_auto_map_topic_alias_send(true)` is used and all topic aliases are in use,
+    /// the least recently used alias is overwritten by a new topic. This counter is
+    /// incremented each time that happens. A rapidly growing value indicates that the
+    /// peer's Topic Alias Maximum is too small for the set of topics being published.
+    /// The counter is cumulative for the lifetime of the connection object.
+    ///
+    /// # Returns
+    ///
+    /// The number of evicted topic aliases
+    pub fn topic_alias_eviction_count(&self) -> u64 {
+        self.topic_alias_eviction_count
+    }
+
+    /// Require a client identifier in received v5.0 CONNECT packets
+    ///
+    /// MQTT v5.0 allows a client to send an empty client identifier and let the
+    /// server assign one. When enabled, a received CONNECT with an empty client
+    /// identifier is rejected with a CONNACK of `ClientIdentifierNotValid`.
+    /// This setting is only meaningful for the server role.
+    ///
+    /// # Parameters
+    ///
+    /// * `require` - Whether to reject an empty client identifier
+    pub fn set_require_client_id(&mut self, require: bool) {
+        self.require_client_id = require;
+    }
+
+    /// Set the maximum length of the client identifier in received CONNECT packets
+    ///
+    /// MQTT v3.1.1 only guarantees client identifiers of up to 23 UTF-8 encoded bytes,
+    /// though servers may allow longer ones. When set, a received CONNECT with a longer
+    /// client identifier is rejected with a CONNACK of `IdentifierRejected` (v3.1.1)
+    /// or `ClientIdentifierNotValid` (v5.0).
+    /// This setting is only meaningful for the server role.
+    ///
+    /// # Parameters
+    ///
+    /// * `max_length` - The maximum length in bytes, or `None` for no limit (default)
+    pub fn set_max_client_id_length(&mut self, max_length: Option<usize>) {
+        self.max_client_id_length = max_length;
+    }
+
+    /// Set the minimum keep-alive accepted from clients
+    ///
+    /// When set, a received CONNECT requesting a non-zero keep-alive smaller than
+    /// `min_keep_alive` is handled according to `set_reject_below_min_keep_alive()`.
+    /// By default, a v5.0 connection raises the keep-alive: the value is reported by
+    /// `server_keep_alive()` and added as ServerKeepAlive by `build_v5_0_connack()`.
+    /// MQTT v3.1.1 cannot tell the client a different keep-alive, so such a CONNECT is
+    /// always rejected with a CONNACK of `ServerUnavailable`.
+    /// This setting is only meaningful for the server role.
+    ///
+    /// # Parameters
+    ///
+    /// * `min_keep_alive` - The minimum keep-alive in seconds, or `None` for no limit (default)
+    pub fn set_min_keep_alive(&mut self, min_keep_alive: Option<u16>) {
+        self.min_keep_alive = min_keep_alive;
+    }
+
+    /// Set whether a v5.0 CONNECT below the minimum keep-alive is rejected
+    ///
+    /// When enabled, a received v5.0 CONNECT requesting a keep-alive below the value
+    /// set by `set_min_keep_alive()` is rejected with a CONNACK of
+    /// `ImplementationSpecificError`, and `NotifyError(KeepAliveBelowMinimum)` is emitted.
+    ///
+    /// # Parameters
+    ///
+    /// * `reject` - Whether to reject instead of raising the keep-alive. The default is `false`.
+    pub fn set_reject_below_min_keep_alive(&mut self, reject: bool) {
+        self.reject_below_min_keep_alive = reject;
+    }
+
+    /// Get the ServerKeepAlive to be sent in CONNACK
+    ///
+    /// Returns the minimum keep-alive if the keep-alive requested by the received
+    /// v5.0 CONNECT was raised by `set_min_keep_alive()`, otherwise `None`.
+    pub fn server_keep_alive(&self) -> Option<u16> {
+        self.server_keep_alive
+    }
+
+    /// Build a v5.0 CONNACK reflecting the adjusted keep-alive
+    ///
+    /// If the reason code is `Success` and the keep-alive was raised by
+    /// `set_min_keep_alive()`, a ServerKeepAlive property is added to `props` unless it
+    /// already contains one. Sending the CONNACK updates the PINGREQ receive timer.
+    ///
+    /// # Parameters
+    ///
+    /// * `session_present` - The session present flag
+    /// * `reason_code` - The reason code
+    /// * `props` - The CONNACK properties
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(v5_0::Connack)` - The built CONNACK
+    /// * `Err(MqttError)` - The properties are invalid for CONNACK
+    pub fn build_v5_0_connack(
+        &self,
+        session_present: bool,
+        reason_code: ConnectReasonCode,
+        mut props: Vec<Property>,
+    ) -> Result<v5_0::Connack, MqttError> {
+        if reason_code == ConnectReasonCode::Success {
+            if let Some(keep_alive) = self.server_keep_alive {
+                if !props
+                    .iter()
+                    .any(|prop| matches!(prop, Property::ServerKeepAlive(_)))
+                {
+                    props.push(ServerKeepAlive::new(keep_alive)?.into());
+                }
+            }
+        }
+        v5_0::Connack::builder()
+            .session_present(session_present)
+            .reason_code(reason_code)
+            .props(props)
+            .build()
+    }
+
+    /// Set whether SUBACK and UNSUBACK with an unknown packet identifier are ignored
+    ///
+    /// By default, a received SUBACK or UNSUBACK whose packet identifier does not
+    /// belong to a sent SUBSCRIBE or UNSUBSCRIBE is treated as a protocol error and the
+    /// connection is closed. When enabled, such a packet is still notified with
+    /// `NotifyPacketReceived`, but the connection stays open. This is intended for
+    /// applications that manage packet identifiers externally.
+    ///
+    /// # Parameters
+    ///
+    /// * `ignore` - Whether to accept unknown packet identifiers. The default is `false`.
+    pub fn set_ignore_unknown_suback(&mut self, ignore: bool) {
+        self.ignore_unknown_suback = ignore;
+    }
+
+    /// Set the maximum number of packets processed by one `recv()` call
+    ///
+    /// `recv()` processes complete packets in the cursor one after another. When a
+    /// limit is set, it stops after processing the given number of packets and leaves
+    /// the remaining data in the cursor for the next call. This keeps the number of
+    /// events returned by one call bounded.
+    ///
+    /// # Parameters
+    ///
+    /// * `max` - The maximum number of packets, or `None` for no limit (default)
+    pub fn set_max_packets_per_recv(&mut self, max: Option<usize>) {
+        self.max_packets_per_recv = max;
+    }
+
+    /// Reject received PUBLISH packets whose topic name starts with `$`
+    ///
+    /// Topics starting with `$` such as `$SYS` are reserved for the server, so
+    /// clients must not publish to them. When enabled on a server, such a PUBLISH
+    /// is not notified to the application and is rejected as follows:
+    /// - v5.0 QoS 1: PUBACK with `TopicNameInvalid`
+    /// - v5.0 QoS 2: PUBREC with `TopicNameInvalid`
+    /// - v5.0 QoS 0: DISCONNECT with `TopicNameInvalid`
+    /// - v3.1.1: the connection is closed and `TopicNameInvalid` is notified
+    ///
+    /// The default is `false`. This setting has no effect on clients.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to reject PUBLISH to `$`-prefixed topics
+    pub fn set_reject_dollar_publish(&mut self, enable: bool) {
+        self.reject_dollar_publish = enable;
+    }
+
+    /// Notify received packets together with their automatic response state
+    ///
+    /// When enabled, `recv()` emits `NotifyPacketReceivedEx` instead of
+    /// `NotifyPacketReceived`. Its `auto_responded` field is `true` if the response
+    /// to the packet has been requested to send automatically by the
+    /// `set_auto_pub_response()` or `set_auto_ping_response()` settings.
+    /// The default is `false`.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to emit `NotifyPacketReceivedEx`
+    pub fn set_notify_auto_responded(&mut self, enable: bool) {
+        self.notify_auto_responded = enable;
+    }
+
+    /// Notify the received packet that caused an error
+    ///
+    /// When enabled, `recv()` emits `NotifyErrorContext` right after each `NotifyError`
+    /// caused by a received packet. It carries the packet type and, if the packet has
+    /// one, the packet identifier. Extracting them costs a little for every received
+    /// packet, so the default is `false`.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to emit `NotifyErrorContext`
+    pub fn set_error_context(&mut self, enable: bool) {
+        self.error_context = enable;
+    }
+
+    /// Notify when the store becomes empty
+    ///
+    /// When enabled, `recv()` emits `NotifyStoreEmpty` after a received PUBACK, PUBCOMP,
+    /// or PUBREC with an error reason code erases the last stored packet.
+    /// The default is `false`.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to emit `NotifyStoreEmpty`
+    pub fn set_notify_store_empty(&mut self, enable: bool) {
+        self.notify_store_empty = enable;
+    }
+
+    /// Notify tolerated protocol irregularities
+    ///
+    /// When enabled, `recv()` emits `NotifyWarning` when a received packet deviates
+    /// from the specification in a way that does not affect the connection, for example
+    /// a DISCONNECT whose reason code is not allowed for the sender's role.
+    /// The default is `false`.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to emit `NotifyWarning`
+    pub fn set_notify_warning(&mut self, enable: bool) {
+        self.notify_warning = enable;
+    }
+
+    /// Set the Reason String for automatically generated DISCONNECT packets (v5.0)
+    ///
+    /// DISCONNECT packets generated by the connection itself, such as on protocol
+    /// errors or keep-alive timeouts, carry the given Reason String property.
+    /// If the DISCONNECT would exceed the peer's Maximum Packet Size, the Reason String
+    /// is dropped so that the DISCONNECT can always be sent.
+    /// The default is `None`.
+    ///
+    /// # Parameters
+    ///
+    /// * `reason_string` - The Reason String, or `None` to send no Reason String
+    pub fn set_auto_disconnect_reason_string(&mut self, reason_string: Option<String>) {
+        self.auto_disconnect_reason_string = reason_string;
+    }
+
     /// Enable or disable automatic topic alias replacement for outgoing packets
     ///
     /// When enabled, the connection will automatically apply existing registered
@@ -910,6 +1861,63 @@ where
         self.auto_replace_topic_alias_send = enable;
     }
 
+    /// Enable or disable strict topic alias remapping for incoming packets
+    ///
+    /// MQTT v5.0 allows the peer to remap a topic alias to a different topic by
+    /// sending a PUBLISH with both a topic name and the alias. When enabled, a received
+    /// PUBLISH whose topic alias is already mapped to a different topic is treated as
+    /// `TopicAliasInvalid`. Registering a new alias or repeating the same mapping is
+    /// still allowed.
+    ///
+    /// # Parameters
+    ///
+    /// * `enable` - Whether to reject topic alias remapping. The default is `false`.
+    pub fn set_strict_topic_alias_remap(&mut self, enable: bool) {
+        self.strict_topic_alias_remap = enable;
+    }
+
+    /// Clear all registered topic aliases for incoming packets
+    ///
+    /// Removes every topic alias mapping registered by received PUBLISH packets.
+    /// The negotiated Topic Alias Maximum is kept, so the peer can register
+    /// aliases again. A subsequent PUBLISH that refers to a cleared alias with an
+    /// empty topic name is treated as `TopicAliasInvalid`.
+    pub fn clear_topic_alias_recv(&mut self) {
+        if let Some(ref mut topic_alias_recv) = self.topic_alias_recv {
+            topic_alias_recv.clear();
+        }
+    }
+
+    /// Clear all registered topic aliases for outgoing packets
+    ///
+    /// Removes every topic alias mapping registered by sent PUBLISH packets.
+    /// The negotiated Topic Alias Maximum is kept. A subsequent PUBLISH that refers
+    /// to a cleared alias with an empty topic name is rejected with
+    /// `PacketNotAllowedToSend`.
+    pub fn clear_topic_alias_send(&mut self) {
+        if let Some(ref mut topic_alias_send) = self.topic_alias_send {
+            topic_alias_send.clear();
+        }
+    }
+
+    /// Get the number of active topic alias mappings for outgoing packets
+    ///
+    /// # Returns
+    ///
+    /// The number of registered aliases, or 0 if topic aliases are not enabled
+    pub fn topic_alias_send_len(&self) -> usize {
+        self.topic_alias_send.as_ref().map_or(0, |ta| ta.len())
+    }
+
+    /// Get the number of active topic alias mappings for incoming packets
+    ///
+    /// # Returns
+    ///
+    /// The number of registered aliases, or 0 if topic aliases are not enabled
+    pub fn topic_alias_recv_len(&self) -> usize {
+        self.topic_alias_recv.as_ref().map_or(0, |ta| ta.len())
+    }
+
     /// Set the PINGRESP receive timeout
     ///
     /// Sets the timeout for receiving PINGRESP packets after sending PINGREQ packets.
@@ -922,6 +1930,160 @@ where
         self.pingresp_recv_timeout_ms = timeout_ms;
     }
 
+    /// Set the timeout for completing the CONNECT/CONNACK handshake
+    ///
+    /// When set, a `ConnectTimeout` timer is requested when the connection starts
+    /// connecting, that is, when a client sends CONNECT or a server receives CONNECT.
+    /// The timer is cancelled when CONNACK completes the handshake. If the timer fires
+    /// while the connection is still connecting, the connection is closed.
+    ///
+    /// Keep-alive applies from CONNECT, so the `PingreqRecv` timer is already armed while
+    /// connecting. This timeout is independent of it and is typically shorter, so that a
+    /// stalled handshake is detected without waiting for the keep-alive period.
+    ///
+    /// # Parameters
+    ///
+    /// * `timeout_ms` - The timeout in milliseconds, or `None` to disable it (default)
+    pub fn set_connect_timeout(&mut self, timeout_ms: Option<u64>) {
+        self.connect_timeout_ms = timeout_ms;
+    }
+
+    /// Set the time to live of handled QoS 2 PUBLISH packet IDs
+    ///
+    /// When set, a `Qos2HandledExpiry` timer is requested while handled QoS 2
+    /// PUBLISH packet IDs are held. Each time the timer fires, the IDs that were
+    /// already held when it previously fired are evicted, so an ID is evicted
+    /// between one and two TTL periods after it was handled. A PUBLISH received
+    /// later with an evicted ID is treated as a new message, not as a duplicate.
+    ///
+    /// The setting takes effect the next time a QoS 2 PUBLISH is handled or the
+    /// connection becomes connected.
+    ///
+    /// # Parameters
+    ///
+    /// * `ttl_ms` - The time to live in milliseconds, or `None` to keep handled IDs
+    ///   until PUBREL is received (default)
+    pub fn set_qos2_handled_ttl(&mut self, ttl_ms: Option<u64>) {
+        self.qos2_handled_ttl_ms = ttl_ms;
+    }
+
+    /// Set whether `notify_closed()` clears handled QoS 2 PUBLISH packet IDs
+    ///
+    /// By default, handled QoS 2 PUBLISH packet IDs are cleared on close only if the
+    /// session is not stored. A stored session keeps them, so that a PUBLISH
+    /// retransmitted after reconnection is still detected as a duplicate.
+    /// This setting overrides that decision regardless of the session state.
+    ///
+    /// # Parameters
+    ///
+    /// * `clear` - `Some(true)` to always clear, `Some(false)` to always keep, or `None`
+    ///   to clear only if the session is not stored (default)
+    pub fn set_clear_qos2_on_close(&mut self, clear: Option<bool>) {
+        self.clear_qos2_on_close = clear;
+    }
+
+    /// Set the default QoS used by `publish_default()`
+    ///
+    /// # Parameters
+    ///
+    /// * `qos` - The QoS level. The default is `Qos::AtMostOnce`.
+    pub fn set_default_publish_qos(&mut self, qos: Qos) {
+        self.default_publish_qos = qos;
+    }
+
+    /// Set the default retain flag used by `publish_default()`
+    ///
+    /// # Parameters
+    ///
+    /// * `retain` - The retain flag. The default is `false`.
+    pub fn set_default_publish_retain(&mut self, retain: bool) {
+        self.default_publish_retain = retain;
+    }
+
+    /// Set the packet observer
+    ///
+    /// The observer is called for every packet the connection requests to send
+    /// (`RequestSendPacket`) and every packet it notifies as received
+    /// (`NotifyPacketReceived`), in the same order as the returned events.
+    /// This provides a single tap point for loggers, recorders, and replayers.
+    ///
+    /// # Parameters
+    ///
+    /// * `observer` - The observer function, or `None` to remove it
+    pub fn set_packet_observer(
+        &mut self,
+        observer: Option<fn(Direction, &GenericPacket<PacketIdType>)>,
+    ) {
+        self.packet_observer = observer;
+    }
+
+    /// Set the transform applied to outgoing packets
+    ///
+    /// The transform is applied to the packet passed to `send()` and `checked_send()`,
+    /// before the packet is validated and stored. It is a hook for proxies and
+    /// gateways, for example to rewrite topics or inject properties. Packets generated
+    /// by the connection itself, such as automatic responses, PINGREQ, and stored
+    /// packets resent after CONNACK, are not transformed.
+    ///
+    /// Because the transformed packet is what gets validated, it is checked against
+    /// the peer's Maximum Packet Size, and it is the copy that is stored and resent
+    /// on reconnection. The transform must not change the packet type or the packet
+    /// identifier.
+    ///
+    /// # Parameters
+    ///
+    /// * `transform` - The transform function, or `None` to remove it
+    pub fn set_outgoing_transform(
+        &mut self,
+        transform: Option<fn(GenericPacket<PacketIdType>) -> GenericPacket<PacketIdType>>,
+    ) {
+        self.outgoing_transform = transform;
+    }
+
+    /// Enable or disable capturing the bytes of the last sent packet
+    ///
+    /// When enabled, the serialized bytes of the packet in the most recent
+    /// `RequestSendPacket` event are kept and can be retrieved with
+    /// `last_sent_bytes()`. Disabling the capture drops the kept bytes.
+    ///
+    /// # Parameters
+    ///
+    /// * `capture` - `true` to capture the last sent packet. The default is `false`.
+    pub fn set_capture_last_sent(&mut self, capture: bool) {
+        self.capture_last_sent = capture;
+        if !capture {
+            self.last_sent_bytes = None;
+        }
+    }
+
+    /// Get the serialized bytes of the last sent packet
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the packet in the most recent `RequestSendPacket` event, or `None`
+    /// if capturing is disabled or no packet has been sent since it was enabled
+    pub fn last_sent_bytes(&self) -> Option<&[u8]> {
+        self.last_sent_bytes.as_deref()
+    }
+
+    /// Set the callback called when the connection becomes disconnected
+    ///
+    /// The callback is called once per connection, when it is disconnected for the
+    /// first time, regardless of the path that caused it, e.g. sending or receiving
+    /// DISCONNECT, a CONNACK with a failure reason, keep alive timeout, a protocol error,
+    /// or `notify_closed()`. It is not called again until a new connection is started.
+    ///
+    /// Calling the callback does not change the connection status. The status becomes
+    /// disconnected only by sending DISCONNECT, sending a CONNACK with a failure reason,
+    /// or `notify_closed()`, as without the callback.
+    ///
+    /// # Parameters
+    ///
+    /// * `callback` - The callback function, or `None` to remove it
+    pub fn set_on_disconnected(&mut self, callback: Option<fn(DisconnectCause)>) {
+        self.on_disconnected = callback;
+    }
+
     /// Acquire a new packet ID for outgoing packets
     ///
     /// # Returns
@@ -931,6 +2093,18 @@ where
         self.pid_man.acquire_unique_id()
     }
 
+    /// Get the packet ID that `acquire_packet_id()` would return next
+    ///
+    /// The packet ID is not acquired, so calling this method repeatedly returns
+    /// the same value until a packet ID is acquired, registered, or released.
+    ///
+    /// # Returns
+    ///
+    /// The next packet ID, or `None` if all packet IDs are in use
+    pub fn peek_next_packet_id(&self) -> Option<PacketIdType> {
+        self.pid_man.peek_next_id()
+    }
+
     /// Register a packet ID as in use
     ///
     /// Manually registers a specific packet ID as being in use, preventing
@@ -974,6 +2148,50 @@ where
         events
     }
 
+    /// Export the packet IDs in use as contiguous ranges
+    ///
+    /// Returns the packet IDs in use, including those acquired by the application but
+    /// not sent yet, as inclusive ranges in ascending order. This is more compact than
+    /// the full set of IDs when persisting a session, especially for `u32` packet IDs.
+    ///
+    /// # Returns
+    ///
+    /// A vector of `(low, high)` inclusive ranges of packet IDs in use
+    pub fn export_packet_id_ranges(&self) -> Vec<(PacketIdType, PacketIdType)> {
+        self.pid_man.export_ranges()
+    }
+
+    /// Import the packet IDs in use from ranges exported by `export_packet_id_ranges()`
+    ///
+    /// Replaces the packet IDs in use with the given ranges, typically used when
+    /// resuming a session from persistent storage. Call this after `restore_packets()`;
+    /// the packet IDs of the stored packets stay in use even if the ranges don't cover them.
+    ///
+    /// # Parameters
+    ///
+    /// * `ranges` - The `(low, high)` inclusive ranges of packet IDs in use
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if successful, or `PacketIdentifierInvalid` if a range is empty or
+    /// contains 0, or `PacketIdentifierConflict` if ranges overlap.
+    /// The packet IDs in use are unchanged on error.
+    pub fn import_packet_id_ranges(
+        &mut self,
+        ranges: &[(PacketIdType, PacketIdType)],
+    ) -> Result<(), MqttError> {
+        let mut pid_man = PacketIdManager::new();
+        pid_man.import_ranges(ranges)?;
+        for packet in self.store.get_stored() {
+            let packet_id = packet.packet_id();
+            if !pid_man.is_used_id(packet_id) {
+                pid_man.register_id(packet_id)?;
+            }
+        }
+        self.pid_man = pid_man;
+        Ok(())
+    }
+
     /// Get the set of QoS 2 PUBLISH packet IDs that have been handled
     ///
     /// Returns a copy of the set containing packet IDs of QoS 2 PUBLISH packets
@@ -996,6 +2214,135 @@ where
     /// * `pids` - A `HashSet` containing packet IDs of previously handled QoS 2 PUBLISH packets
     pub fn restore_qos2_publish_handled(&mut self, pids: HashSet<PacketIdType>) {
         self.qos2_publish_handled = pids;
+        self.qos2_publish_handled_aged.clear();
+    }
+
+    /// Clear the set of QoS 2 PUBLISH packet IDs that have been handled
+    ///
+    /// Drops the receive-side duplicate detection state, for example after the
+    /// application has durably processed all QoS 2 messages. A PUBLISH received
+    /// afterwards with a previously handled packet ID is treated as a new message.
+    pub fn clear_qos2_publish_handled(&mut self) {
+        self.qos2_publish_handled.clear();
+        self.qos2_publish_handled_aged.clear();
+    }
+
+    /// Abort an in-progress inbound QoS 2 flow
+    ///
+    /// Drops the receive-side state held for the given packet ID without waiting
+    /// for the handshake to complete. If the connection is connected and PUBREC has
+    /// not been sent yet, a response is sent so that the peer can finish its side
+    /// of the flow:
+    /// - v5.0: PUBREC with `UnspecifiedError`, which terminates the flow
+    /// - v3.1.1: PUBREC, the following PUBREL is answered with PUBCOMP as usual
+    ///
+    /// If PUBREC has already been sent, for example by automatic publish responses,
+    /// no response is sent; the following PUBREL is answered with PUBCOMP as usual.
+    /// If the packet ID is not part of an inbound QoS 2 flow, nothing happens.
+    ///
+    /// # Parameters
+    ///
+    /// * `packet_id` - The packet ID of the inbound QoS 2 PUBLISH to abort
+    ///
+    /// # Returns
+    ///
+    /// Events generated from sending the response, if any
+    pub fn abort_inbound_qos2(
+        &mut self,
+        packet_id: PacketIdType,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        let handled = self.qos2_publish_handled.remove(&packet_id);
+        let recv = self.publish_recv.remove(&packet_id);
+        let pubrec_pending = self.publish_ack_pending.remove(&packet_id) == Some(Qos::ExactlyOnce);
+        if !handled && !recv {
+            return Vec::new();
+        }
+        if self.status != ConnectionStatus::Connected || !pubrec_pending {
+            return Vec::new();
+        }
+
+        let events = match self.protocol_version {
+            Version::V3_1_1 => {
+                let pubrec = v3_1_1::GenericPubrec::<PacketIdType>::builder()
+                    .packet_id(packet_id)
+                    .build()
+                    .unwrap();
+                self.process_send_v3_1_1_pubrec(pubrec)
+            }
+            Version::V5_0 => {
+                let pubrec = v5_0::GenericPubrec::<PacketIdType>::builder()
+                    .packet_id(packet_id)
+                    .reason_code(PubrecReasonCode::UnspecifiedError)
+                    .build()
+                    .unwrap();
+                self.process_send_v5_0_pubrec(pubrec)
+            }
+            _ => Vec::new(),
+        };
+        self.observe_packets(&events);
+        events
+    }
+
+    /// Acknowledge multiple received PUBLISH packets at once
+    ///
+    /// When automatic publish responses are disabled, received QoS 1 and QoS 2 PUBLISH
+    /// packets wait for the application to acknowledge them. This method sends the
+    /// acknowledgement for each of the given packet IDs in order: PUBACK for QoS 1 and
+    /// PUBREC for QoS 2. A packet ID that has no received PUBLISH waiting for an
+    /// acknowledgement produces a `NotifyError` with `PacketIdentifierInvalid`.
+    ///
+    /// # Parameters
+    ///
+    /// * `packet_ids` - The packet IDs of the received PUBLISH packets to acknowledge
+    ///
+    /// # Returns
+    ///
+    /// Events generated from sending the acknowledgements
+    pub fn acknowledge_many(
+        &mut self,
+        packet_ids: &[PacketIdType],
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        let mut events = Vec::new();
+        for &packet_id in packet_ids {
+            let qos = self.publish_ack_pending.get(&packet_id).copied();
+            match (qos, self.protocol_version) {
+                (Some(Qos::AtLeastOnce), Version::V3_1_1) => {
+                    let puback = v3_1_1::GenericPuback::<PacketIdType>::builder()
+                        .packet_id(packet_id)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v3_1_1_puback(puback));
+                }
+                (Some(Qos::AtLeastOnce), Version::V5_0) => {
+                    let puback = v5_0::GenericPuback::<PacketIdType>::builder()
+                        .packet_id(packet_id)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v5_0_puback(puback));
+                }
+                (Some(Qos::ExactlyOnce), Version::V3_1_1) => {
+                    let pubrec = v3_1_1::GenericPubrec::<PacketIdType>::builder()
+                        .packet_id(packet_id)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v3_1_1_pubrec(pubrec));
+                }
+                (Some(Qos::ExactlyOnce), Version::V5_0) => {
+                    let pubrec = v5_0::GenericPubrec::<PacketIdType>::builder()
+                        .packet_id(packet_id)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v5_0_pubrec(pubrec));
+                }
+                _ => {
+                    events.push(GenericEvent::NotifyError(
+                        MqttError::PacketIdentifierInvalid,
+                    ));
+                }
+            }
+        }
+        self.observe_packets(&events);
+        events
     }
 
     /// Restore previously stored packets
@@ -1101,6 +2448,94 @@ where
         self.store.get_stored()
     }
 
+    /// Get stored PUBLISH packets
+    ///
+    /// Returns only the stored PUBLISH packets (QoS 1 and QoS 2 that have not yet
+    /// received PUBACK/PUBREC), in the same order as `get_stored_packets()`.
+    ///
+    /// # Returns
+    ///
+    /// Vector of stored PUBLISH packets
+    pub fn stored_publishes(&self) -> Vec<GenericStorePacket<PacketIdType>> {
+        self.store
+            .get_stored()
+            .into_iter()
+            .filter(|p| {
+                matches!(
+                    p,
+                    GenericStorePacket::V3_1_1Publish(_) | GenericStorePacket::V5_0Publish(_)
+                )
+            })
+            .collect()
+    }
+
+    /// Get stored PUBREL packets
+    ///
+    /// Returns only the stored PUBREL packets (QoS 2 flows waiting for PUBCOMP),
+    /// in the same order as `get_stored_packets()`.
+    ///
+    /// # Returns
+    ///
+    /// Vector of stored PUBREL packets
+    pub fn stored_pubrels(&self) -> Vec<GenericStorePacket<PacketIdType>> {
+        self.store
+            .get_stored()
+            .into_iter()
+            .filter(|p| {
+                matches!(
+                    p,
+                    GenericStorePacket::V3_1_1Pubrel(_) | GenericStorePacket::V5_0Pubrel(_)
+                )
+            })
+            .collect()
+    }
+
+    /// Resend a single stored packet by packet ID
+    ///
+    /// Requests retransmission of the stored PUBLISH or PUBREL packet with the given
+    /// packet ID. Stored PUBLISH packets already have the DUP flag set. The packet
+    /// stays in the store until the corresponding response is received.
+    ///
+    /// # Parameters
+    ///
+    /// * `packet_id` - Packet ID of the stored packet to resend
+    ///
+    /// # Returns
+    ///
+    /// A `RequestSendPacket` event on success, followed by a `RequestTimerReset` event
+    /// for the PINGREQ send timer if it is enabled. Otherwise a `NotifyError` event:
+    /// - `PacketNotAllowedToSend` if the connection is not connected
+    /// - `PacketIdentifierInvalid` if no packet is stored with the packet ID
+    /// - `PacketTooLarge` if the packet exceeds the peer's Maximum Packet Size
+    pub fn resend_stored_packet(
+        &mut self,
+        packet_id: PacketIdType,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        if self.status != ConnectionStatus::Connected {
+            return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
+        }
+        let packet = match self.store.get(packet_id) {
+            Some(packet) => packet,
+            None => {
+                error!("packet_id {packet_id} is not stored");
+                return vec![GenericEvent::NotifyError(
+                    MqttError::PacketIdentifierInvalid,
+                )];
+            }
+        };
+        if !self.validate_maximum_packet_size_send(packet.size()) {
+            return vec![GenericEvent::NotifyError(MqttError::PacketTooLarge)];
+        }
+
+        let mut events = vec![GenericEvent::RequestSendPacket {
+            packet: packet.clone().into(),
+            release_packet_id_if_send_error: None,
+        }];
+        self.send_post_process(&mut events);
+        self.observe_packets(&events);
+        events
+    }
+
     /// Erase a stored QoS 1 or QoS 2 PUBLISH packet by packet ID
     ///
     /// This method removes a stored PUBLISH packet from the connection's retransmission store
@@ -1168,40 +2603,253 @@ where
         self.protocol_version
     }
 
-    /// Regulate packet for store (remove/resolve topic alias)
-    ///
-    /// This method prepares a V5.0 publish packet for storage by resolving topic aliases
-    /// and removing TopicAlias properties to ensure the packet can be retransmitted correctly.
-    pub fn regulate_for_store(
-        &self,
-        mut packet: v5_0::GenericPublish<PacketIdType>,
-    ) -> Result<v5_0::GenericPublish<PacketIdType>, MqttError> {
-        if packet.topic_name().is_empty() {
-            // Topic is empty, need to resolve from topic alias
-            if let Some(topic_alias) = Self::get_topic_alias_from_props(packet.props()) {
-                if let Some(ref topic_alias_send) = self.topic_alias_send {
-                    if let Some(topic) = topic_alias_send.peek(topic_alias) {
-                        // Found topic for alias, add topic and remove alias property
-                        packet = packet.remove_topic_alias_add_topic(topic.to_string())?;
-                    } else {
-                        return Err(MqttError::PacketNotRegulated);
+    /// Get the maximum remaining length of a packet
+    ///
+    /// This is the maximum value that can be encoded as Variable Byte Integer,
+    /// and is the same for MQTT v3.1.1 and v5.0.
+    ///
+    /// # Returns
+    ///
+    /// The maximum remaining length (268435455)
+    pub fn max_remaining_length(&self) -> u32 {
+        MQTT_MAX_REMAINING_LENGTH
+    }
+
+    /// Get the maximum total size of a packet that can be sent
+    ///
+    /// The size includes the fixed header and the remaining length field.
+    /// Without a limit, this is `max_remaining_length()` plus 5 bytes of header.
+    /// If the peer has advertised a smaller Maximum Packet Size (v5.0), that value
+    /// is returned instead.
+    ///
+    /// # Returns
+    ///
+    /// The maximum total packet size in bytes for sending
+    pub fn max_total_packet_size(&self) -> u32 {
+        MQTT_MAX_PACKET_SIZE.min(self.maximum_packet_size_send)
+    }
+
+    /// Get the role the connection acts as
+    ///
+    /// For `Client` and `Server` connections, the role is always known from the type
+    /// parameter. For `Any` connections, the role is inferred from the handshake:
+    /// sending CONNECT makes it a client, receiving CONNECT makes it a server.
+    /// Applications can use this to route an `Any` connection to client or server
+    /// specific handling once the handshake has started.
+    ///
+    /// # Returns
+    ///
+    /// The role, or `None` if an `Any` connection has not sent or received CONNECT yet
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// match connection.inferred_role() {
+    ///     Some(mqtt::role::ConnectionRole::Client) => { /* client side handling */ }
+    ///     Some(mqtt::role::ConnectionRole::Server) => { /* server side handling */ }
+    ///     None => { /* handshake not started yet */ }
+    /// }
+    /// ```
+    pub fn inferred_role(&self) -> Option<ConnectionRole> {
+        if Role::IS_CLIENT {
+            Some(ConnectionRole::Client)
+        } else if Role::IS_SERVER {
+            Some(ConnectionRole::Server)
+        } else if !self.role_determined {
+            None
+        } else if self.is_client {
+            Some(ConnectionRole::Client)
+        } else {
+            Some(ConnectionRole::Server)
+        }
+    }
+
+    /// Check whether the connection currently runs in client mode
+    ///
+    /// Returns the runtime client/server flag set when CONNECT is sent (client mode)
+    /// or received (server mode). Unlike `inferred_role()`, this does not consult the
+    /// `Role` type parameter, so it returns `false` for any connection that has not
+    /// sent CONNECT yet, including `Client` connections.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the connection has sent CONNECT, `false` otherwise
+    pub fn is_client_mode(&self) -> bool {
+        self.is_client
+    }
+
+    /// Regulate packet for store (remove/resolve topic alias)
+    ///
+    /// This method prepares a V5.0 publish packet for storage by resolving topic aliases
+    /// and removing TopicAlias properties to ensure the packet can be retransmitted correctly.
+    pub fn regulate_for_store(
+        &self,
+        mut packet: v5_0::GenericPublish<PacketIdType>,
+    ) -> Result<v5_0::GenericPublish<PacketIdType>, MqttError> {
+        if packet.topic_name().is_empty() {
+            // Topic is empty, need to resolve from topic alias
+            if let Some(topic_alias) = Self::get_topic_alias_from_props(packet.props()) {
+                if let Some(ref topic_alias_send) = self.topic_alias_send {
+                    if let Some(topic) = topic_alias_send.peek(topic_alias) {
+                        // Found topic for alias, add topic and remove alias property
+                        packet = packet.remove_topic_alias_add_topic(topic.to_string())?;
+                    } else {
+                        return Err(MqttError::PacketNotRegulated);
+                    }
+                } else {
+                    return Err(MqttError::PacketNotRegulated);
+                }
+            } else {
+                return Err(MqttError::PacketNotRegulated);
+            }
+        } else {
+            // Topic is not empty, just remove TopicAlias property if present
+            packet = packet.remove_topic_alias();
+        }
+
+        Ok(packet)
+    }
+
+    // private
+
+    fn recv_packet_context(raw_packet: &RawPacket) -> (Option<PacketType>, Option<u32>) {
+        let packet_type = PacketType::try_from(raw_packet.packet_type()).ok();
+        let data = raw_packet.data_as_slice();
+        let offset = match packet_type {
+            Some(PacketType::Publish) => {
+                // The packet identifier follows the topic name only if QoS is not 0
+                let qos = (raw_packet.flags() >> 1) & 0x03;
+                match data.get(..2) {
+                    Some(len) if qos != 0 => {
+                        Some(2 + u16::from_be_bytes([len[0], len[1]]) as usize)
+                    }
+                    _ => None,
+                }
+            }
+            Some(
+                PacketType::Puback
+                | PacketType::Pubrec
+                | PacketType::Pubrel
+                | PacketType::Pubcomp
+                | PacketType::Subscribe
+                | PacketType::Suback
+                | PacketType::Unsubscribe
+                | PacketType::Unsuback,
+            ) => Some(0),
+            _ => None,
+        };
+        let size = PacketIdType::Buffer::default().as_ref().len();
+        let packet_id = offset
+            .and_then(|offset| data.get(offset..offset + size))
+            .and_then(|buf| PacketIdType::from_buffer(buf).to_u32());
+        (packet_type, packet_id)
+    }
+
+    fn add_error_context(
+        events: Vec<GenericEvent<PacketIdType>>,
+        packet_type: Option<PacketType>,
+        packet_id: Option<u32>,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        let mut result = Vec::with_capacity(events.len());
+        for event in events {
+            let error = match event {
+                GenericEvent::NotifyError(error) => Some(error),
+                _ => None,
+            };
+            result.push(event);
+            if let Some(error) = error {
+                result.push(GenericEvent::NotifyErrorContext(MqttErrorContext {
+                    error,
+                    packet_type,
+                    packet_id,
+                }));
+            }
+        }
+        result
+    }
+
+    fn mark_auto_responded(
+        events: Vec<GenericEvent<PacketIdType>>,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        let sent: Vec<PacketType> = events
+            .iter()
+            .filter_map(|event| match event {
+                GenericEvent::RequestSendPacket { packet, .. } => Some(packet.packet_type()),
+                _ => None,
+            })
+            .collect();
+        events
+            .into_iter()
+            .map(|event| match event {
+                GenericEvent::NotifyPacketReceived(packet) => {
+                    let responses: &[PacketType] = match packet.packet_type() {
+                        PacketType::Publish => &[PacketType::Puback, PacketType::Pubrec],
+                        PacketType::Pubrec => &[PacketType::Pubrel],
+                        PacketType::Pubrel => &[PacketType::Pubcomp],
+                        PacketType::Pingreq => &[PacketType::Pingresp],
+                        _ => &[],
+                    };
+                    let auto_responded = sent.iter().any(|sent| responses.contains(sent));
+                    GenericEvent::NotifyPacketReceivedEx {
+                        packet,
+                        auto_responded,
+                    }
+                }
+                event => event,
+            })
+            .collect()
+    }
+
+    fn is_dollar_publish_rejected(&self, topic_name: &str) -> bool {
+        if self.reject_dollar_publish && !self.is_client && topic_name.starts_with('$') {
+            error!("PUBLISH to $-prefixed topic {topic_name} is rejected");
+            return true;
+        }
+        false
+    }
+
+    fn set_disconnected(&mut self, cause: DisconnectCause) {
+        self.status = ConnectionStatus::Disconnected;
+        self.notify_disconnected(cause);
+    }
+
+    fn notify_disconnected(&mut self, cause: DisconnectCause) {
+        if !self.disconnected_notified {
+            self.disconnected_notified = true;
+            if let Some(callback) = self.on_disconnected {
+                callback(cause);
+            }
+        }
+    }
+
+    fn observe_packets(&mut self, events: &[GenericEvent<PacketIdType>]) {
+        if self.capture_last_sent {
+            let last_sent = events.iter().rev().find_map(|event| match event {
+                GenericEvent::RequestSendPacket { packet, .. } => Some(packet),
+                _ => None,
+            });
+            if let Some(packet) = last_sent {
+                self.last_sent_bytes = Some(packet.to_continuous_buffer());
+            }
+        }
+        if let Some(observer) = self.packet_observer {
+            for event in events {
+                match event {
+                    GenericEvent::RequestSendPacket { packet, .. } => {
+                        observer(Direction::Send, packet)
                     }
-                } else {
-                    return Err(MqttError::PacketNotRegulated);
+                    GenericEvent::NotifyPacketReceived(packet)
+                    | GenericEvent::NotifyPacketReceivedEx { packet, .. } => {
+                        observer(Direction::Recv, packet)
+                    }
+                    _ => {}
                 }
-            } else {
-                return Err(MqttError::PacketNotRegulated);
             }
-        } else {
-            // Topic is not empty, just remove TopicAlias property if present
-            packet = packet.remove_topic_alias();
         }
-
-        Ok(packet)
     }
 
-    // private
-
     /// Initialize connection state based on client/server role
     ///
     /// Resets all connection-specific state including:
@@ -1220,18 +2868,28 @@ where
         self.topic_alias_send = None;
         self.topic_alias_recv = None;
         self.publish_recv.clear();
+        self.publish_ack_pending.clear();
         self.need_store = false;
         self.pid_suback.clear();
         self.pid_unsuback.clear();
         self.is_client = is_client;
+        self.role_determined = true;
         self.pingreq_keep_alive_ms = 0;
         self.pingreq_server_keep_alive_ms = None;
+        self.session_expiry_interval = None;
+        self.server_keep_alive = None;
+        self.retain_available = true;
+        self.wildcard_subscription_available = true;
+        self.shared_subscription_available = true;
+        self.subscription_identifier_available = true;
+        self.auth_method_received = false;
     }
 
     fn clear_store_related(&mut self) {
         self.pid_man.clear();
         self.pid_puback.clear();
         self.pid_pubrec.clear();
+        self.pid_pubrel.clear();
         self.pid_pubcomp.clear();
         self.store.clear();
     }
@@ -1322,7 +2980,7 @@ where
 
         let mut events = Vec::new();
         self.initialize(true);
-        self.status = ConnectionStatus::Connecting;
+        self.enter_connecting(&mut events);
 
         self.pingreq_keep_alive_ms = packet.keep_alive() as u64 * 1000;
 
@@ -1360,9 +3018,10 @@ where
 
         let mut events = Vec::new();
         self.initialize(true);
-        self.status = ConnectionStatus::Connecting;
+        self.enter_connecting(&mut events);
 
         self.pingreq_keep_alive_ms = packet.keep_alive() as u64 * 1000;
+        self.session_expiry_interval = Some(0);
 
         // Handle clean_start flag
         if packet.clean_start() {
@@ -1383,8 +3042,11 @@ where
                     debug_assert!(val.val() != 0, "MaximumPacketSize must not be 0");
                     self.maximum_packet_size_recv = val.val();
                 }
-                Property::SessionExpiryInterval(val) if val.val() != 0 => {
-                    self.need_store = true;
+                Property::SessionExpiryInterval(val) => {
+                    self.session_expiry_interval = Some(val.val());
+                    if val.val() != 0 {
+                        self.need_store = true;
+                    }
                 }
                 _ => {
                     // Ignore other properties (equivalent to [](auto const&){} in C++)
@@ -1416,13 +3078,13 @@ where
             release_packet_id_if_send_error: None,
         });
         if rc != ConnectReturnCode::Accepted {
-            self.status = ConnectionStatus::Disconnected;
+            self.set_disconnected(DisconnectCause::ConnackFailure);
             self.cancel_timers(&mut events);
             events.push(GenericEvent::RequestClose);
             return events;
         }
 
-        self.status = ConnectionStatus::Connected;
+        self.enter_connected(&mut events);
         events.extend(self.send_stored());
         self.send_post_process(&mut events);
 
@@ -1460,6 +3122,9 @@ where
                         debug_assert!(val.val() != 0, "MaximumPacketSize must not be 0");
                         self.maximum_packet_size_recv = val.val();
                     }
+                    Property::SessionExpiryInterval(val) => {
+                        self.session_expiry_interval = Some(val.val());
+                    }
                     Property::ServerKeepAlive(val) => {
                         let val = val.val();
                         if val == 0 {
@@ -1490,13 +3155,13 @@ where
         });
 
         if rc != ConnectReasonCode::Success {
-            self.status = ConnectionStatus::Disconnected;
+            self.set_disconnected(DisconnectCause::ConnackFailure);
             self.cancel_timers(&mut events);
             events.push(GenericEvent::RequestClose);
             return events;
         }
 
-        self.status = ConnectionStatus::Connected;
+        self.enter_connected(&mut events);
 
         events.extend(self.send_stored());
         self.send_post_process(&mut events);
@@ -1514,10 +3179,7 @@ where
         if packet.qos() == Qos::AtLeastOnce || packet.qos() == Qos::ExactlyOnce {
             // Register packet ID for QoS 1 or 2
             let packet_id = packet.packet_id().unwrap();
-            if self.status != ConnectionStatus::Connected
-                && !self.need_store
-                && !self.offline_publish
-            {
+            if !self.is_publish_sendable_in_state(packet.qos()) {
                 events.push(GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend));
                 if self.pid_man.is_used_id(packet_id) {
                     self.pid_man.release_id(packet_id);
@@ -1545,7 +3207,7 @@ where
             } else {
                 self.pid_puback.insert(packet_id);
             }
-        } else if self.status != ConnectionStatus::Connected {
+        } else if !self.is_publish_sendable_in_state(packet.qos()) {
             events.push(GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend));
             return events;
         }
@@ -1574,10 +3236,7 @@ where
         let mut topic_alias_validated = false;
         if packet.qos() == Qos::AtLeastOnce || packet.qos() == Qos::ExactlyOnce {
             let packet_id = packet.packet_id().unwrap();
-            if self.status != ConnectionStatus::Connected
-                && !self.need_store
-                && !self.offline_publish
-            {
+            if !self.is_publish_sendable_in_state(packet.qos()) {
                 events.push(GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend));
                 if self.pid_man.is_used_id(packet_id) {
                     self.pid_man.release_id(packet_id);
@@ -1630,7 +3289,7 @@ where
             } else {
                 self.pid_puback.insert(packet_id);
             }
-        } else if self.status != ConnectionStatus::Connected {
+        } else if !self.is_publish_sendable_in_state(packet.qos()) {
             events.push(GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend));
             return events;
         }
@@ -1689,6 +3348,9 @@ where
                         packet = packet.remove_topic_add_topic_alias(found_ta);
                     } else {
                         let lru_ta = topic_alias_send.get_lru_alias();
+                        if topic_alias_send.peek(lru_ta).is_some() {
+                            self.topic_alias_eviction_count += 1;
+                        }
                         topic_alias_send.insert_or_update(packet.topic_name(), lru_ta);
                         packet = packet.add_topic_alias(lru_ta);
                     }
@@ -1708,23 +3370,23 @@ where
         }
 
         // Check receive_maximum for sending (QoS 1 and 2 packets)
-        if packet.qos() == Qos::AtLeastOnce || packet.qos() == Qos::ExactlyOnce {
-            if let Some(max) = self.publish_send_max {
-                if self.publish_send_count == max {
-                    events.push(GenericEvent::NotifyError(MqttError::ReceiveMaximumExceeded));
-                    if let Some(packet_id) = packet_id_opt {
-                        if self.pid_man.is_used_id(packet_id) {
-                            self.pid_man.release_id(packet_id);
-                            self.store.erase_publish(packet_id);
-                            self.pid_puback.remove(&packet_id);
-                            self.pid_pubrec.remove(&packet_id);
-                            events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
-                        }
+        if (packet.qos() == Qos::AtLeastOnce || packet.qos() == Qos::ExactlyOnce)
+            && self.publish_send_max.is_some()
+        {
+            if self.is_publish_send_window_full() {
+                events.push(GenericEvent::NotifyError(MqttError::ReceiveMaximumExceeded));
+                if let Some(packet_id) = packet_id_opt {
+                    if self.pid_man.is_used_id(packet_id) {
+                        self.pid_man.release_id(packet_id);
+                        self.store.erase_publish(packet_id);
+                        self.pid_puback.remove(&packet_id);
+                        self.pid_pubrec.remove(&packet_id);
+                        events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
                     }
-                    return events;
                 }
-                self.publish_send_count += 1;
+                return events;
             }
+            self.publish_send_count += 1;
         }
 
         if self.status == ConnectionStatus::Connected {
@@ -1746,6 +3408,7 @@ where
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
         let mut events = Vec::new();
+        self.publish_ack_pending.remove(&packet.packet_id());
 
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -1769,6 +3432,7 @@ where
 
         let mut events = Vec::new();
         self.publish_recv.remove(&packet.packet_id());
+        self.publish_ack_pending.remove(&packet.packet_id());
 
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -1787,6 +3451,7 @@ where
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
         let mut events = Vec::new();
+        self.publish_ack_pending.remove(&packet.packet_id());
 
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -1810,6 +3475,7 @@ where
 
         let mut events = Vec::new();
         let packet_id = packet.packet_id();
+        self.publish_ack_pending.remove(&packet_id);
 
         if let Some(rc) = packet.reason_code() {
             if rc.is_failure() {
@@ -1831,7 +3497,7 @@ where
         &mut self,
         packet: v3_1_1::GenericPubrel<PacketIdType>,
     ) -> Vec<GenericEvent<PacketIdType>> {
-        if self.status != ConnectionStatus::Connected && !self.need_store {
+        if !self.is_pubrel_sendable_in_state() {
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
         let mut events = Vec::new();
@@ -1843,6 +3509,11 @@ where
             ));
             return events;
         }
+        if !self.pid_pubrel.remove(&packet_id) {
+            error!("packet_id {packet_id} PUBREL must be sent after PUBREC is received");
+            events.push(GenericEvent::NotifyError(MqttError::PubrecNotReceived));
+            return events;
+        }
         if self.need_store {
             self.store.add(packet.clone().try_into().unwrap()).unwrap();
         }
@@ -1866,7 +3537,7 @@ where
         if !self.validate_maximum_packet_size_send(packet.size()) {
             return vec![GenericEvent::NotifyError(MqttError::PacketTooLarge)];
         }
-        if self.status != ConnectionStatus::Connected && !self.need_store {
+        if !self.is_pubrel_sendable_in_state() {
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
 
@@ -1879,6 +3550,11 @@ where
             ));
             return events;
         }
+        if !self.pid_pubrel.remove(&packet_id) {
+            error!("packet_id {packet_id} PUBREL must be sent after PUBREC is received");
+            events.push(GenericEvent::NotifyError(MqttError::PubrecNotReceived));
+            return events;
+        }
         if self.need_store {
             self.store.add(packet.clone().try_into().unwrap()).unwrap();
         }
@@ -1957,7 +3633,7 @@ where
             ));
             return events;
         }
-        self.pid_suback.insert(packet_id);
+        self.pid_suback.insert(packet_id, packet.entries().len());
 
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -1993,7 +3669,7 @@ where
             ));
             return events;
         }
-        self.pid_suback.insert(packet_id);
+        self.pid_suback.insert(packet_id, packet.entries().len());
 
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -2246,7 +3922,7 @@ where
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
         let mut events = Vec::new();
-        self.status = ConnectionStatus::Disconnected;
+        self.set_disconnected(DisconnectCause::SentDisconnect);
         self.cancel_timers(&mut events);
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -2260,6 +3936,14 @@ where
     pub(crate) fn process_send_v5_0_disconnect(
         &mut self,
         packet: v5_0::Disconnect,
+    ) -> Vec<GenericEvent<PacketIdType>> {
+        self.send_v5_0_disconnect(packet, DisconnectCause::SentDisconnect)
+    }
+
+    fn send_v5_0_disconnect(
+        &mut self,
+        packet: v5_0::Disconnect,
+        cause: DisconnectCause,
     ) -> Vec<GenericEvent<PacketIdType>> {
         if !self.validate_maximum_packet_size_send(packet.size()) {
             return vec![GenericEvent::NotifyError(MqttError::PacketTooLarge)];
@@ -2269,7 +3953,7 @@ where
         }
 
         let mut events = Vec::new();
-        self.status = ConnectionStatus::Disconnected;
+        self.set_disconnected(cause);
         self.cancel_timers(&mut events);
         events.push(GenericEvent::RequestSendPacket {
             packet: packet.into(),
@@ -2287,7 +3971,10 @@ where
         if !self.validate_maximum_packet_size_send(packet.size()) {
             return vec![GenericEvent::NotifyError(MqttError::PacketTooLarge)];
         }
-        if self.status == ConnectionStatus::Disconnected {
+        if !self.is_auth_sendable_in_state() {
+            if self.status != ConnectionStatus::Disconnected {
+                error!("AUTH is not allowed without Authentication Method in CONNECT");
+            }
             return vec![GenericEvent::NotifyError(MqttError::PacketNotAllowedToSend)];
         }
 
@@ -2301,17 +3988,22 @@ where
         events
     }
 
+    fn pingreq_send_interval_ms(&self) -> u64 {
+        // Priority 3
+        let mut ms: u64 = self.pingreq_keep_alive_ms;
+        if let Some(timeout_ms) = self.pingreq_user_send_interval_ms {
+            // Priority 1
+            ms = timeout_ms;
+        } else if let Some(timeout_ms) = self.pingreq_server_keep_alive_ms {
+            // Priority 2
+            ms = timeout_ms;
+        }
+        ms
+    }
+
     fn send_post_process(&mut self, events: &mut Vec<GenericEvent<PacketIdType>>) {
         if self.is_client {
-            // Priority 3
-            let mut ms: u64 = self.pingreq_keep_alive_ms;
-            if let Some(timeout_ms) = self.pingreq_user_send_interval_ms {
-                // Priority 1
-                ms = timeout_ms;
-            } else if let Some(timeout_ms) = self.pingreq_server_keep_alive_ms {
-                // Priority 2
-                ms = timeout_ms;
-            }
+            let ms = self.pingreq_send_interval_ms();
             if ms > 0 {
                 self.pingreq_send_set = true;
                 events.push(GenericEvent::RequestTimerReset {
@@ -2363,13 +4055,24 @@ where
             // If the packet size is over 268434555, feed() return an error.
             // maximum_packet_size_recv is set by sending CONNECT or CONNACK packet.
             // So DISCONNECT packet is the right choice to notify the error.
-            let disconnect_packet = v5_0::Disconnect::builder()
-                .reason_code(DisconnectReasonCode::PacketTooLarge)
-                .build()
-                .unwrap();
+            error!(
+                "received packet (type {}) size {total_size} exceeds MaximumPacketSize {}",
+                raw_packet.packet_type(),
+                self.maximum_packet_size_recv
+            );
+            let error = if self.has_omittable_properties(&raw_packet) {
+                // ReasonString / UserProperty are optional, so the sender must omit
+                // them instead of exceeding the limit.
+                error!("ReasonString and UserProperty must be omitted to fit MaximumPacketSize");
+                MqttError::OptionalPropertiesTooLarge
+            } else {
+                MqttError::PacketTooLarge
+            };
+            let disconnect_packet =
+                self.build_auto_disconnect(DisconnectReasonCode::PacketTooLarge);
             // Send disconnect packet directly without generic constraints
             events.extend(self.process_send_v5_0_disconnect(disconnect_packet));
-            events.push(GenericEvent::NotifyError(MqttError::PacketTooLarge));
+            events.push(GenericEvent::NotifyError(error));
             return events;
         }
 
@@ -2439,6 +4142,11 @@ where
                         // DISCONNECT
                         events.extend(self.process_recv_v3_1_1_disconnect(raw_packet));
                     }
+                    15 => {
+                        // AUTH is not defined in v3.1.1
+                        error!("AUTH packet is not allowed in MQTT v3.1.1");
+                        events.push(GenericEvent::NotifyError(MqttError::ProtocolError));
+                    }
                     // invalid packet type
                     _ => {
                         events.push(GenericEvent::NotifyError(MqttError::MalformedPacket));
@@ -2548,18 +4256,49 @@ where
         events
     }
 
+    fn is_client_id_length_valid(&self, client_id: &str) -> bool {
+        self.max_client_id_length
+            .is_none_or(|max_length| client_id.len() <= max_length)
+    }
+
+    fn is_keep_alive_below_min(&self, keep_alive: u16) -> bool {
+        keep_alive != 0 && self.min_keep_alive.is_some_and(|min| keep_alive < min)
+    }
+
     fn process_recv_v3_1_1_connect(
         &mut self,
         raw_packet: RawPacket,
     ) -> Vec<GenericEvent<PacketIdType>> {
         let mut events = Vec::new();
         if self.status != ConnectionStatus::Disconnected {
-            Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+            self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
             return events;
         }
-        self.status = ConnectionStatus::Connecting;
+        self.enter_connecting(&mut events);
         match v3_1_1::Connect::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
+                if !self.is_client_id_length_valid(packet.client_id()) {
+                    let connack = v3_1_1::Connack::builder()
+                        .return_code(ConnectReturnCode::IdentifierRejected)
+                        .session_present(false)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v3_1_1_connack(connack));
+                    events.push(GenericEvent::NotifyError(
+                        MqttError::ClientIdentifierNotValid,
+                    ));
+                    return events;
+                }
+                if self.is_keep_alive_below_min(packet.keep_alive()) {
+                    let connack = v3_1_1::Connack::builder()
+                        .return_code(ConnectReturnCode::ServerUnavailable)
+                        .session_present(false)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v3_1_1_connack(connack));
+                    events.push(GenericEvent::NotifyError(MqttError::KeepAliveBelowMinimum));
+                    return events;
+                }
                 self.initialize(false);
                 if packet.keep_alive() > 0 {
                     self.pingreq_recv_timeout_ms = (packet.keep_alive() as u64) * 1000 * 3 / 2;
@@ -2572,6 +4311,10 @@ where
                 events.extend(self.refresh_pingreq_recv());
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
+            Err(MqttError::MalformedPacket) => {
+                // v3.1.1 has no CONNACK return code for malformed packets, just close
+                self.handle_v3_1_1_error(MqttError::MalformedPacket, &mut events);
+            }
             Err(e) => {
                 let rc = match e {
                     MqttError::ClientIdentifierNotValid => ConnectReturnCode::IdentifierRejected,
@@ -2600,20 +4343,58 @@ where
         raw_packet: RawPacket,
     ) -> Vec<GenericEvent<PacketIdType>> {
         let mut events = Vec::new();
+        if self.status == ConnectionStatus::Connecting {
+            // DISCONNECT cannot be sent before CONNACK, just close
+            self.notify_disconnected(DisconnectCause::ProtocolError);
+            self.cancel_timers(&mut events);
+            events.push(GenericEvent::RequestClose);
+            events.push(GenericEvent::NotifyError(MqttError::ProtocolError));
+            return events;
+        }
         if self.status != ConnectionStatus::Disconnected {
             self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
             return events;
         }
-        self.status = ConnectionStatus::Connecting;
+        self.enter_connecting(&mut events);
         match v5_0::Connect::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
+                if (self.require_client_id && packet.client_id().is_empty())
+                    || !self.is_client_id_length_valid(packet.client_id())
+                {
+                    let connack = v5_0::Connack::builder()
+                        .reason_code(ConnectReasonCode::ClientIdentifierNotValid)
+                        .session_present(false)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v5_0_connack(connack));
+                    events.push(GenericEvent::NotifyError(
+                        MqttError::ClientIdentifierNotValid,
+                    ));
+                    return events;
+                }
+                let keep_alive_below_min = self.is_keep_alive_below_min(packet.keep_alive());
+                if keep_alive_below_min && self.reject_below_min_keep_alive {
+                    let connack = v5_0::Connack::builder()
+                        .reason_code(ConnectReasonCode::ImplementationSpecificError)
+                        .session_present(false)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v5_0_connack(connack));
+                    events.push(GenericEvent::NotifyError(MqttError::KeepAliveBelowMinimum));
+                    return events;
+                }
                 self.initialize(false);
                 if packet.keep_alive() > 0 {
                     self.pingreq_recv_timeout_ms = (packet.keep_alive() as u64) * 1000 * 3 / 2;
                 }
+                if keep_alive_below_min {
+                    // Applied to the PINGREQ receive timer when CONNACK is sent
+                    self.server_keep_alive = self.min_keep_alive;
+                }
                 if packet.clean_start() {
                     self.clear_store_related();
                 }
+                self.session_expiry_interval = Some(0);
                 packet.props().iter().for_each(|prop| match prop {
                     Property::TopicAliasMaximum(p) => {
                         self.topic_alias_send = Some(TopicAliasSend::new(p.val()));
@@ -2624,8 +4405,14 @@ where
                     Property::MaximumPacketSize(p) => {
                         self.maximum_packet_size_send = p.val();
                     }
-                    Property::SessionExpiryInterval(p) if p.val() != 0 => {
-                        self.need_store = true;
+                    Property::SessionExpiryInterval(p) => {
+                        self.session_expiry_interval = Some(p.val());
+                        if p.val() != 0 {
+                            self.need_store = true;
+                        }
+                    }
+                    Property::AuthenticationMethod(_) => {
+                        self.auth_method_received = true;
                     }
                     _ => {}
                 });
@@ -2641,6 +4428,8 @@ where
                     MqttError::UnsupportedProtocolVersion => {
                         ConnectReasonCode::UnsupportedProtocolVersion
                     }
+                    MqttError::MalformedPacket => ConnectReasonCode::MalformedPacket,
+                    MqttError::ProtocolError => ConnectReasonCode::ProtocolError,
                     _ => ConnectReasonCode::UnspecifiedError,
                 };
                 let connack = v5_0::Connack::builder()
@@ -2666,19 +4455,21 @@ where
         match v3_1_1::Connack::parse(raw_packet.data_as_slice()) {
             Ok((packet, _consumed)) => {
                 if packet.return_code() == ConnectReturnCode::Accepted {
-                    self.status = ConnectionStatus::Connected;
+                    self.enter_connected(&mut events);
                     if packet.session_present() {
                         events.extend(self.send_stored());
                     } else {
                         self.clear_store_related();
                     }
+                } else {
+                    self.notify_disconnected(DisconnectCause::ConnackFailure);
                 }
                 events.push(GenericEvent::NotifyPacketReceived(
                     GenericPacket::V3_1_1Connack(packet),
                 ));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -2694,7 +4485,7 @@ where
         match v5_0::Connack::parse(raw_packet.data_as_slice()) {
             Ok((packet, _consumed)) => {
                 if packet.reason_code() == ConnectReasonCode::Success {
-                    self.status = ConnectionStatus::Connected;
+                    self.enter_connected(&mut events);
 
                     // Process properties
                     for prop in packet.props() {
@@ -2733,7 +4524,20 @@ where
                                     }
                                 }
                             }
+                            Property::RetainAvailable(val) => {
+                                self.retain_available = val.val() == 1;
+                            }
+                            Property::WildcardSubscriptionAvailable(val) => {
+                                self.wildcard_subscription_available = val.val() == 1;
+                            }
+                            Property::SharedSubscriptionAvailable(val) => {
+                                self.shared_subscription_available = val.val() == 1;
+                            }
+                            Property::SubscriptionIdentifierAvailable(val) => {
+                                self.subscription_identifier_available = val.val() == 1;
+                            }
                             Property::SessionExpiryInterval(val) => {
+                                self.session_expiry_interval = Some(val.val());
                                 if val.val() == 0 {
                                     self.need_store = false;
                                     self.clear_store_related();
@@ -2752,6 +4556,8 @@ where
                     } else {
                         self.clear_store_related();
                     }
+                } else {
+                    self.notify_disconnected(DisconnectCause::ConnackFailure);
                 }
                 events.push(GenericEvent::NotifyPacketReceived(
                     GenericPacket::V5_0Connack(packet),
@@ -2780,6 +4586,10 @@ where
             PacketData::Publish(arc) => {
                 match v3_1_1::GenericPublish::parse(flags, arc.clone()) {
                     Ok((packet, _consumed)) => {
+                        if self.is_dollar_publish_rejected(packet.topic_name()) {
+                            self.handle_v3_1_1_error(MqttError::TopicNameInvalid, &mut events);
+                            return events;
+                        }
                         match packet.qos() {
                             Qos::AtMostOnce => {
                                 events.extend(self.refresh_pingreq_recv());
@@ -2796,6 +4606,8 @@ where
                                         .build()
                                         .unwrap();
                                     events.extend(self.process_send_v3_1_1_puback(puback));
+                                } else {
+                                    self.publish_ack_pending.insert(packet_id, Qos::AtLeastOnce);
                                 }
                                 events.extend(self.refresh_pingreq_recv());
                                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
@@ -2803,6 +4615,10 @@ where
                             Qos::ExactlyOnce => {
                                 let packet_id = packet.packet_id().unwrap();
                                 let already_handled = !self.qos2_publish_handled.insert(packet_id);
+                                if !already_handled {
+                                    self.qos2_publish_handled_aged.remove(&packet_id);
+                                    self.arm_qos2_handled_expiry(&mut events);
+                                }
 
                                 if self.status == ConnectionStatus::Connected
                                     && (self.auto_pub_response || already_handled)
@@ -2812,6 +4628,8 @@ where
                                         .build()
                                         .unwrap();
                                     events.extend(self.process_send_v3_1_1_pubrec(pubrec));
+                                } else if !already_handled {
+                                    self.publish_ack_pending.insert(packet_id, Qos::ExactlyOnce);
                                 }
                                 events.extend(self.refresh_pingreq_recv());
                                 if !already_handled {
@@ -2821,7 +4639,7 @@ where
                         }
                     }
                     Err(e) => {
-                        Self::handle_v3_1_1_error(e, &mut events);
+                        self.handle_v3_1_1_error(e, &mut events);
                     }
                 }
             }
@@ -2882,7 +4700,10 @@ where
                                 }
                                 self.publish_recv.insert(packet_id);
 
-                                if !self.qos2_publish_handled.insert(packet_id) {
+                                if self.qos2_publish_handled.insert(packet_id) {
+                                    self.qos2_publish_handled_aged.remove(&packet_id);
+                                    self.arm_qos2_handled_expiry(&mut events);
+                                } else {
                                     already_handled = true;
                                 }
                                 if self.status == ConnectionStatus::Connected
@@ -2897,7 +4718,12 @@ where
                         }
 
                         // Topic Alias handling
-                        if packet.topic_name().is_empty() {
+                        if packet.topic_name().is_empty() && self.topic_alias_recv.is_none() {
+                            // Topic aliases are not enabled, an empty topic name is never allowed
+                            error!("empty topic name received without topic alias enabled");
+                            self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
+                            return events;
+                        } else if packet.topic_name().is_empty() {
                             // Extract topic from topic_alias
                             if let Some(ta) = Self::get_topic_alias_from_props(packet.props()) {
                                 if ta == 0
@@ -2952,11 +4778,51 @@ where
                                     return events;
                                 }
                                 if let Some(ref mut topic_alias_recv) = self.topic_alias_recv {
+                                    if self.strict_topic_alias_remap
+                                        && topic_alias_recv
+                                            .peek(ta)
+                                            .is_some_and(|topic| topic != packet.topic_name())
+                                    {
+                                        error!("topic alias {ta} remapped to a different topic");
+                                        self.handle_v5_0_error(
+                                            MqttError::TopicAliasInvalid,
+                                            &mut events,
+                                        );
+                                        return events;
+                                    }
                                     topic_alias_recv.insert_or_update(packet.topic_name(), ta);
                                 }
                             }
                         }
 
+                        if self.is_dollar_publish_rejected(packet.topic_name()) {
+                            match packet.qos() {
+                                Qos::AtLeastOnce => {
+                                    let puback = v5_0::GenericPuback::builder()
+                                        .packet_id(packet.packet_id().unwrap())
+                                        .reason_code(PubackReasonCode::TopicNameInvalid)
+                                        .build()
+                                        .unwrap();
+                                    events.extend(self.process_send_v5_0_puback(puback));
+                                }
+                                Qos::ExactlyOnce => {
+                                    let pubrec = v5_0::GenericPubrec::builder()
+                                        .packet_id(packet.packet_id().unwrap())
+                                        .reason_code(PubrecReasonCode::TopicNameInvalid)
+                                        .build()
+                                        .unwrap();
+                                    events.extend(self.process_send_v5_0_pubrec(pubrec));
+                                }
+                                Qos::AtMostOnce => {
+                                    self.handle_v5_0_error(
+                                        MqttError::TopicNameInvalid,
+                                        &mut events,
+                                    );
+                                }
+                            }
+                            return events;
+                        }
+
                         // Send response packets
                         if puback_send {
                             let puback = v5_0::GenericPuback::builder()
@@ -2973,6 +4839,12 @@ where
                             events.extend(self.process_send_v5_0_pubrec(pubrec));
                         }
 
+                        if !already_handled && !puback_send && !pubrec_send {
+                            if let Some(packet_id) = packet.packet_id() {
+                                self.publish_ack_pending.insert(packet_id, packet.qos());
+                            }
+                        }
+
                         // Refresh PINGREQ receive timer
                         events.extend(self.refresh_pingreq_recv());
 
@@ -3008,19 +4880,27 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 if self.pid_puback.remove(&packet_id) {
-                    self.store.erase(ResponsePacket::V3_1_1Puback, packet_id);
+                    let erased = self.store.erase(ResponsePacket::V3_1_1Puback, packet_id);
                     if self.pid_man.is_used_id(packet_id) {
                         self.pid_man.release_id(packet_id);
                         events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    if self.notify_store_empty && erased && self.store.is_empty() {
+                        events.push(GenericEvent::NotifyStoreEmpty);
+                    }
+                } else if self.pid_pubrec.contains(&packet_id)
+                    || self.pid_pubcomp.contains(&packet_id)
+                {
+                    error!("PUBACK received for QoS2 packet_id: {packet_id}");
+                    self.handle_v3_1_1_error(MqttError::AckQosMismatch, &mut events);
                 } else {
-                    Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+                    self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3037,7 +4917,7 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 if self.pid_puback.remove(&packet_id) {
-                    self.store.erase(ResponsePacket::V5_0Puback, packet_id);
+                    let erased = self.store.erase(ResponsePacket::V5_0Puback, packet_id);
                     if self.pid_man.is_used_id(packet_id) {
                         self.pid_man.release_id(packet_id);
                         events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
@@ -3047,6 +4927,14 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    if self.notify_store_empty && erased && self.store.is_empty() {
+                        events.push(GenericEvent::NotifyStoreEmpty);
+                    }
+                } else if self.pid_pubrec.contains(&packet_id)
+                    || self.pid_pubcomp.contains(&packet_id)
+                {
+                    error!("PUBACK received for QoS2 packet_id: {packet_id}");
+                    self.handle_v5_0_error(MqttError::AckQosMismatch, &mut events);
                 } else {
                     self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
                 }
@@ -3070,7 +4958,8 @@ where
                 let packet_id = packet.packet_id();
                 if self.pid_pubrec.remove(&packet_id) {
                     self.store.erase(ResponsePacket::V3_1_1Pubrec, packet_id);
-                    if self.auto_pub_response && self.status == ConnectionStatus::Connected {
+                    self.pid_pubrel.insert(packet_id);
+                    if self.auto_pub_response_active() {
                         let pubrel = v3_1_1::GenericPubrel::<PacketIdType>::builder()
                             .packet_id(packet_id)
                             .build()
@@ -3079,12 +4968,15 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                } else if self.pid_puback.contains(&packet_id) {
+                    error!("PUBREC received for QoS1 packet_id: {packet_id}");
+                    self.handle_v3_1_1_error(MqttError::AckQosMismatch, &mut events);
                 } else {
-                    Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+                    self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3101,10 +4993,13 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 if self.pid_pubrec.remove(&packet_id) {
-                    self.store.erase(ResponsePacket::V5_0Pubrec, packet_id);
+                    let erased = self.store.erase(ResponsePacket::V5_0Pubrec, packet_id);
                     let reason_code = packet.reason_code();
-                    if reason_code.is_none() || reason_code.unwrap() == PubrecReasonCode::Success {
-                        if self.auto_pub_response && self.status == ConnectionStatus::Connected {
+                    let completed = !(reason_code.is_none()
+                        || reason_code.unwrap() == PubrecReasonCode::Success);
+                    if !completed {
+                        self.pid_pubrel.insert(packet_id);
+                        if self.auto_pub_response_active() {
                             let pubrel = v5_0::GenericPubrel::<PacketIdType>::builder()
                                 .packet_id(packet_id)
                                 .build()
@@ -3122,6 +5017,13 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    // A successful PUBREC continues the QoS 2 flow with PUBREL
+                    if self.notify_store_empty && completed && erased && self.store.is_empty() {
+                        events.push(GenericEvent::NotifyStoreEmpty);
+                    }
+                } else if self.pid_puback.contains(&packet_id) {
+                    error!("PUBREC received for QoS1 packet_id: {packet_id}");
+                    self.handle_v5_0_error(MqttError::AckQosMismatch, &mut events);
                 } else {
                     self.handle_v5_0_error(
                         MqttError::from(DisconnectReasonCode::ProtocolError),
@@ -3147,7 +5049,7 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 self.qos2_publish_handled.remove(&packet_id);
-                if self.auto_pub_response && self.status == ConnectionStatus::Connected {
+                if self.auto_pub_response_active() {
                     let pubcomp = v3_1_1::GenericPubcomp::<PacketIdType>::builder()
                         .packet_id(packet_id)
                         .build()
@@ -3158,7 +5060,7 @@ where
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3174,22 +5076,19 @@ where
         match v5_0::GenericPubrel::<PacketIdType>::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
-                let removed = self.qos2_publish_handled.remove(&packet_id);
-                if self.auto_pub_response && self.status == ConnectionStatus::Connected {
-                    if removed {
-                        let pubcomp = v5_0::GenericPubcomp::<PacketIdType>::builder()
-                            .packet_id(packet_id)
-                            .build()
-                            .unwrap();
-                        events.extend(self.process_send_v5_0_pubcomp(pubcomp));
-                    } else {
-                        let pubcomp = v5_0::GenericPubcomp::<PacketIdType>::builder()
-                            .packet_id(packet_id)
-                            .reason_code(result_code::PubcompReasonCode::PacketIdentifierNotFound)
-                            .build()
-                            .unwrap();
-                        events.extend(self.process_send_v5_0_pubcomp(pubcomp));
-                    }
+                self.qos2_publish_handled.remove(&packet_id);
+                if self.auto_pub_response_active() {
+                    // A PUBREL for an id that is no longer held (e.g. a replay after
+                    // PUBCOMP has already been sent) is still answered with PUBCOMP(Success).
+                    // PacketIdentifierNotFound (0x92) is not used: the id is forgotten once
+                    // PUBCOMP is sent, so a replay caused by a lost PUBCOMP cannot be told
+                    // apart from a truly unknown id, and answering 0x92 would make the
+                    // sender treat an already delivered message as failed.
+                    let pubcomp = v5_0::GenericPubcomp::<PacketIdType>::builder()
+                        .packet_id(packet_id)
+                        .build()
+                        .unwrap();
+                    events.extend(self.process_send_v5_0_pubcomp(pubcomp));
                 }
                 events.extend(self.refresh_pingreq_recv());
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
@@ -3212,19 +5111,22 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 if self.pid_pubcomp.remove(&packet_id) {
-                    self.store.erase(ResponsePacket::V3_1_1Pubcomp, packet_id);
+                    let erased = self.store.erase(ResponsePacket::V3_1_1Pubcomp, packet_id);
                     if self.pid_man.is_used_id(packet_id) {
                         self.pid_man.release_id(packet_id);
                         events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    if self.notify_store_empty && erased && self.store.is_empty() {
+                        events.push(GenericEvent::NotifyStoreEmpty);
+                    }
                 } else {
-                    Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+                    self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3241,7 +5143,7 @@ where
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
                 if self.pid_pubcomp.remove(&packet_id) {
-                    self.store.erase(ResponsePacket::V5_0Pubcomp, packet_id);
+                    let erased = self.store.erase(ResponsePacket::V5_0Pubcomp, packet_id);
                     if self.pid_man.is_used_id(packet_id) {
                         self.pid_man.release_id(packet_id);
                         events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
@@ -3251,6 +5153,9 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    if self.notify_store_empty && erased && self.store.is_empty() {
+                        events.push(GenericEvent::NotifyStoreEmpty);
+                    }
                 } else {
                     self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
                 }
@@ -3275,7 +5180,7 @@ where
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3310,19 +5215,32 @@ where
         match v3_1_1::GenericSuback::<PacketIdType>::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
-                if self.pid_suback.remove(&packet_id) {
-                    if self.pid_man.is_used_id(packet_id) {
-                        self.pid_man.release_id(packet_id);
-                        events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                match self.pid_suback.get(&packet_id).copied() {
+                    Some(entries_len) if entries_len == packet.return_codes().len() => {
+                        self.pid_suback.remove(&packet_id);
+                        if self.pid_man.is_used_id(packet_id) {
+                            self.pid_man.release_id(packet_id);
+                            events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                        }
+                        events.extend(self.refresh_pingreq_recv());
+                        events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    }
+                    Some(_) => {
+                        error!("packet_id {packet_id} SUBACK code count doesn't match SUBSCRIBE");
+                        self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+                    }
+                    None if self.ignore_unknown_suback => {
+                        warn!("packet_id {packet_id} SUBACK for unknown SUBSCRIBE is ignored");
+                        events.extend(self.refresh_pingreq_recv());
+                        events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    }
+                    None => {
+                        self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                     }
-                    events.extend(self.refresh_pingreq_recv());
-                    events.push(GenericEvent::NotifyPacketReceived(packet.into()));
-                } else {
-                    Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3338,15 +5256,28 @@ where
         match v5_0::GenericSuback::<PacketIdType>::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
                 let packet_id = packet.packet_id();
-                if self.pid_suback.remove(&packet_id) {
-                    if self.pid_man.is_used_id(packet_id) {
-                        self.pid_man.release_id(packet_id);
-                        events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                match self.pid_suback.get(&packet_id).copied() {
+                    Some(entries_len) if entries_len == packet.reason_codes().len() => {
+                        self.pid_suback.remove(&packet_id);
+                        if self.pid_man.is_used_id(packet_id) {
+                            self.pid_man.release_id(packet_id);
+                            events.push(GenericEvent::NotifyPacketIdReleased(packet_id));
+                        }
+                        events.extend(self.refresh_pingreq_recv());
+                        events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    }
+                    Some(_) => {
+                        error!("packet_id {packet_id} SUBACK code count doesn't match SUBSCRIBE");
+                        self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
+                    }
+                    None if self.ignore_unknown_suback => {
+                        warn!("packet_id {packet_id} SUBACK for unknown SUBSCRIBE is ignored");
+                        events.extend(self.refresh_pingreq_recv());
+                        events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                    }
+                    None => {
+                        self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
                     }
-                    events.extend(self.refresh_pingreq_recv());
-                    events.push(GenericEvent::NotifyPacketReceived(packet.into()));
-                } else {
-                    self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
@@ -3369,7 +5300,7 @@ where
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3411,12 +5342,16 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                } else if self.ignore_unknown_suback {
+                    warn!("packet_id {packet_id} UNSUBACK for unknown UNSUBSCRIBE is ignored");
+                    events.extend(self.refresh_pingreq_recv());
+                    events.push(GenericEvent::NotifyPacketReceived(packet.into()));
                 } else {
-                    Self::handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
+                    self.handle_v3_1_1_error(MqttError::ProtocolError, &mut events);
                 }
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3439,6 +5374,10 @@ where
                     }
                     events.extend(self.refresh_pingreq_recv());
                     events.push(GenericEvent::NotifyPacketReceived(packet.into()));
+                } else if self.ignore_unknown_suback {
+                    warn!("packet_id {packet_id} UNSUBACK for unknown UNSUBSCRIBE is ignored");
+                    events.extend(self.refresh_pingreq_recv());
+                    events.push(GenericEvent::NotifyPacketReceived(packet.into()));
                 } else {
                     self.handle_v5_0_error(MqttError::ProtocolError, &mut events);
                 }
@@ -3459,11 +5398,7 @@ where
 
         match v3_1_1::Pingreq::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
-                if (Role::IS_SERVER || Role::IS_ANY)
-                    && !self.is_client
-                    && self.auto_ping_response
-                    && self.status == ConnectionStatus::Connected
-                {
+                if self.auto_ping_response_active() {
                     let pingresp = v3_1_1::Pingresp::new();
                     events.extend(self.process_send_v3_1_1_pingresp(pingresp));
                 }
@@ -3471,7 +5406,7 @@ where
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3486,11 +5421,7 @@ where
 
         match v5_0::Pingreq::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
-                if (Role::IS_SERVER || Role::IS_ANY)
-                    && !self.is_client
-                    && self.auto_ping_response
-                    && self.status == ConnectionStatus::Connected
-                {
+                if self.auto_ping_response_active() {
                     let pingresp = v5_0::Pingresp::new();
                     events.extend(self.process_send_v5_0_pingresp(pingresp));
                 }
@@ -3520,7 +5451,7 @@ where
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3557,11 +5488,12 @@ where
 
         match v3_1_1::Disconnect::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
+                self.notify_disconnected(DisconnectCause::RecvDisconnect);
                 self.cancel_timers(&mut events);
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
-                Self::handle_v3_1_1_error(e, &mut events);
+                self.handle_v3_1_1_error(e, &mut events);
             }
         }
 
@@ -3576,7 +5508,24 @@ where
 
         match v5_0::Disconnect::parse(raw_packet.data_as_slice()) {
             Ok((packet, _)) => {
+                self.notify_disconnected(DisconnectCause::RecvDisconnect);
                 self.cancel_timers(&mut events);
+                if let Some(rc) = packet.reason_code() {
+                    // The peer of a client is a server and vice versa
+                    let allowed = if self.is_client {
+                        rc.is_sendable_by_server()
+                    } else {
+                        rc.is_sendable_by_client()
+                    };
+                    if !allowed {
+                        warn!("DISCONNECT reason code {rc} is not allowed for the peer role");
+                        if self.notify_warning {
+                            events.push(GenericEvent::NotifyWarning(
+                                MqttError::DisconnectReasonCodeNotAllowed,
+                            ));
+                        }
+                    }
+                }
                 events.push(GenericEvent::NotifyPacketReceived(packet.into()));
             }
             Err(e) => {
@@ -3603,20 +5552,74 @@ where
         events
     }
 
-    fn handle_v3_1_1_error(e: MqttError, events: &mut Vec<GenericEvent<PacketIdType>>) {
+    fn handle_v3_1_1_error(&mut self, e: MqttError, events: &mut Vec<GenericEvent<PacketIdType>>) {
+        self.notify_disconnected(DisconnectCause::ProtocolError);
         events.push(GenericEvent::RequestClose);
         events.push(GenericEvent::NotifyError(e));
     }
 
     fn handle_v5_0_error(&mut self, e: MqttError, events: &mut Vec<GenericEvent<PacketIdType>>) {
-        let disconnect = v5_0::Disconnect::builder()
-            .reason_code(e.into())
-            .build()
-            .unwrap();
-        events.extend(self.process_send_v5_0_disconnect(disconnect));
+        let disconnect = self.build_auto_disconnect(e.into());
+        events.extend(self.send_v5_0_disconnect(disconnect, DisconnectCause::ProtocolError));
         events.push(GenericEvent::NotifyError(e));
     }
 
+    /// Build an automatically generated v5.0 DISCONNECT
+    ///
+    /// The Reason String set by `set_auto_disconnect_reason_string()` is attached.
+    /// If the packet would exceed the peer's Maximum Packet Size, the optional
+    /// properties are dropped so that the DISCONNECT can always be sent.
+    fn build_auto_disconnect(&self, reason_code: DisconnectReasonCode) -> v5_0::Disconnect {
+        if let Some(reason_string) = &self.auto_disconnect_reason_string {
+            if let Ok(reason_string) = ReasonString::new(reason_string) {
+                if let Ok(disconnect) = v5_0::Disconnect::builder()
+                    .reason_code(reason_code)
+                    .props(vec![reason_string.into()])
+                    .build()
+                {
+                    if disconnect.size() <= self.maximum_packet_size_send as usize {
+                        return disconnect;
+                    }
+                    warn!("Reason String is dropped to fit MaximumPacketSize");
+                }
+            }
+        }
+        v5_0::Disconnect::builder()
+            .reason_code(reason_code)
+            .build()
+            .unwrap()
+    }
+
+    /// Check whether a received CONNACK, PUBACK, PUBREC, PUBREL, PUBCOMP, SUBACK,
+    /// UNSUBACK, DISCONNECT, or AUTH carries ReasonString or UserProperty
+    ///
+    /// These properties are optional, so the sender must omit them if the packet
+    /// would exceed the receiver's Maximum Packet Size.
+    fn has_omittable_properties(&self, raw_packet: &RawPacket) -> bool {
+        let packet_id_size = core::mem::size_of::<<PacketIdType as IsPacketId>::Buffer>();
+        let props_offset = match raw_packet.packet_type() {
+            // Acknowledge Flags and Reason Code
+            2 => 2,
+            // Packet Identifier and Reason Code
+            4..=7 => packet_id_size + 1,
+            // Packet Identifier, the Reason Codes follow the properties
+            9 | 11 => packet_id_size,
+            // Reason Code
+            14 | 15 => 1,
+            _ => return false,
+        };
+        let data = raw_packet.data_as_slice();
+        if data.len() <= props_offset {
+            return false;
+        }
+        match Properties::parse(&data[props_offset..]) {
+            Ok((props, _)) => props
+                .iter()
+                .any(|prop| matches!(prop, Property::ReasonString(_) | Property::UserProperty(_))),
+            Err(_) => false,
+        }
+    }
+
     fn refresh_pingreq_recv(&mut self) -> Vec<GenericEvent<PacketIdType>> {
         let mut events = Vec::new();
         if self.pingreq_recv_timeout_ms != 0 {
@@ -3644,6 +5647,50 @@ where
             self.pingresp_recv_set = false;
             events.push(GenericEvent::RequestTimerCancel(TimerKind::PingrespRecv));
         }
+        if self.connect_timeout_set {
+            self.connect_timeout_set = false;
+            events.push(GenericEvent::RequestTimerCancel(TimerKind::ConnectTimeout));
+        }
+        if self.qos2_handled_expiry_set {
+            self.qos2_handled_expiry_set = false;
+            events.push(GenericEvent::RequestTimerCancel(
+                TimerKind::Qos2HandledExpiry,
+            ));
+        }
+    }
+
+    fn arm_qos2_handled_expiry(&mut self, events: &mut Vec<GenericEvent<PacketIdType>>) {
+        if let Some(ttl_ms) = self.qos2_handled_ttl_ms {
+            if !self.qos2_handled_expiry_set && !self.qos2_publish_handled.is_empty() {
+                self.qos2_handled_expiry_set = true;
+                events.push(GenericEvent::RequestTimerReset {
+                    kind: TimerKind::Qos2HandledExpiry,
+                    duration_ms: ttl_ms,
+                });
+            }
+        }
+    }
+
+    fn enter_connecting(&mut self, events: &mut Vec<GenericEvent<PacketIdType>>) {
+        self.status = ConnectionStatus::Connecting;
+        self.disconnected_notified = false;
+        if let Some(timeout_ms) = self.connect_timeout_ms {
+            self.connect_timeout_set = true;
+            events.push(GenericEvent::RequestTimerReset {
+                kind: TimerKind::ConnectTimeout,
+                duration_ms: timeout_ms,
+            });
+        }
+    }
+
+    fn enter_connected(&mut self, events: &mut Vec<GenericEvent<PacketIdType>>) {
+        self.status = ConnectionStatus::Connected;
+        self.disconnected_notified = false;
+        if self.connect_timeout_set {
+            self.connect_timeout_set = false;
+            events.push(GenericEvent::RequestTimerCancel(TimerKind::ConnectTimeout));
+        }
+        self.arm_qos2_handled_expiry(events);
     }
 
     /// Helper function to extract TopicAlias from properties
@@ -3657,6 +5704,46 @@ where
     }
 }
 
+/// One-line summary of the connection for logging
+///
+/// The summary consists of the role, protocol version, status, the number of
+/// outgoing QoS 1/2 PUBLISH flows in progress, the number of stored packets,
+/// and the keep alive in seconds, e.g.
+/// `Client V5_0 Connected, 3 inflight, 2 stored, keep-alive 60s`.
+/// The role is shown as `Any` until it has been determined.
+impl<Role, PacketIdType> core::fmt::Display for GenericConnection<Role, PacketIdType>
+where
+    Role: RoleType,
+    PacketIdType: IsPacketId,
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let role = match self.inferred_role() {
+            Some(ConnectionRole::Client) => "Client",
+            Some(ConnectionRole::Server) => "Server",
+            None => "Any",
+        };
+        let inflight = self.pid_puback.len()
+            + self.pid_pubrec.len()
+            + self.pid_pubrel.len()
+            + self.pid_pubcomp.len();
+        let keep_alive_ms = if self.is_client {
+            // Includes the ServerKeepAlive override and set_pingreq_send_interval()
+            self.pingreq_send_interval_ms()
+        } else {
+            // pingreq_recv_timeout_ms is 1.5 times the keep alive of the received CONNECT
+            self.pingreq_recv_timeout_ms * 2 / 3
+        };
+        write!(
+            f,
+            "{role} {:?} {:?}, {inflight} inflight, {} stored, keep-alive {}s",
+            self.protocol_version,
+            self.status,
+            self.store.len(),
+            keep_alive_ms / 1000
+        )
+    }
+}
+
 // tests
 
 #[cfg(test)]
@@ -3846,7 +5933,7 @@ mod tests {
         // Set up some state that should be cleared
         connection.publish_send_count = 5;
         connection.need_store = true;
-        connection.pid_suback.insert(123);
+        connection.pid_suback.insert(123, 1);
         connection.pid_unsuback.insert(456);
 
         // Initialize should clear state
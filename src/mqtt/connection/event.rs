@@ -26,8 +26,77 @@ use serde::Serialize;
 
 use crate::mqtt::packet::GenericPacket;
 use crate::mqtt::packet::IsPacketId;
+use crate::mqtt::packet::PacketType;
 use crate::mqtt::result_code::MqttError;
 
+/// Direction of a packet observed on a connection
+///
+/// Passed to the packet observer registered with
+/// `GenericConnection::set_packet_observer()` to indicate whether the packet
+/// is being sent or has been received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Direction {
+    /// The packet is requested to be sent (`RequestSendPacket`)
+    #[serde(rename = "send")]
+    Send,
+
+    /// The packet has been received (`NotifyPacketReceived` or `NotifyPacketReceivedEx`)
+    #[serde(rename = "recv")]
+    Recv,
+}
+
+/// Cause of a transition to the disconnected state
+///
+/// Passed to the callback registered with
+/// `GenericConnection::set_on_disconnected()` to indicate why the connection
+/// has become disconnected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DisconnectCause {
+    /// DISCONNECT packet has been sent by the application
+    #[serde(rename = "sent_disconnect")]
+    SentDisconnect,
+
+    /// DISCONNECT packet has been received
+    #[serde(rename = "recv_disconnect")]
+    RecvDisconnect,
+
+    /// CONNACK with a failure reason code has been sent or received
+    #[serde(rename = "connack_failure")]
+    ConnackFailure,
+
+    /// PINGREQ or PINGRESP has not been received within the keep alive period
+    #[serde(rename = "keep_alive_timeout")]
+    KeepAliveTimeout,
+
+    /// A protocol violation or a malformed packet has been detected
+    #[serde(rename = "protocol_error")]
+    ProtocolError,
+
+    /// The CONNECT/CONNACK handshake has not been completed in time
+    #[serde(rename = "connect_timeout")]
+    ConnectTimeout,
+
+    /// The underlying connection has been closed (`notify_closed()`)
+    #[serde(rename = "closed")]
+    Closed,
+}
+
+/// Context of an error detected while processing a received packet
+///
+/// Emitted by `GenericEvent::NotifyErrorContext` when enabled by
+/// `GenericConnection::set_error_context()`, to tell which packet caused the error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MqttErrorContext {
+    /// The error that occurred
+    pub error: MqttError,
+    /// Type of the received packet, `None` if the fixed header has an invalid type
+    pub packet_type: Option<PacketType>,
+    /// Packet identifier of the received packet, `None` if it has none or it cannot be read
+    pub packet_id: Option<u32>,
+}
+
 /// Represents different types of MQTT timers
 ///
 /// This enum defines the different kinds of timers used in MQTT protocol operations.
@@ -58,6 +127,22 @@ pub enum TimerKind {
     /// timeframe, indicating a potentially disconnected or unresponsive server.
     #[serde(rename = "pingresp_recv")]
     PingrespRecv,
+
+    /// Timer for completing the CONNECT/CONNACK handshake
+    ///
+    /// This timer is used to detect when a connection that has sent or received
+    /// CONNECT does not complete the handshake with CONNACK within the timeframe
+    /// set by `GenericConnection::set_connect_timeout()`.
+    #[serde(rename = "connect_timeout")]
+    ConnectTimeout,
+
+    /// Timer for evicting handled QoS 2 PUBLISH packet IDs
+    ///
+    /// This timer is used to expire the packet IDs kept for QoS 2 duplicate
+    /// detection after the time to live set by
+    /// `GenericConnection::set_qos2_handled_ttl()`.
+    #[serde(rename = "qos2_handled_expiry")]
+    Qos2HandledExpiry,
 }
 
 /// Generic MQTT Event - represents events that occur during MQTT operations
@@ -107,6 +192,25 @@ where
     /// * `GenericPacket<PacketIdType>` - The parsed MQTT packet
     NotifyPacketReceived(GenericPacket<PacketIdType>),
 
+    /// Notification that a packet was received, with its automatic response state
+    ///
+    /// This event is emitted instead of `NotifyPacketReceived` when enabled by
+    /// `GenericConnection::set_notify_auto_responded()`. In addition to the packet,
+    /// it indicates whether the library has already requested to send the response
+    /// (PUBACK, PUBREC, PUBREL, PUBCOMP, or PINGRESP) to the packet automatically,
+    /// so that the application doesn't need to respond to it.
+    ///
+    /// # Fields
+    ///
+    /// * `packet` - The parsed MQTT packet
+    /// * `auto_responded` - Whether the response has been sent automatically
+    NotifyPacketReceivedEx {
+        /// The parsed MQTT packet
+        packet: GenericPacket<PacketIdType>,
+        /// Whether the library has automatically requested to send the response
+        auto_responded: bool,
+    },
+
     /// Request to send a packet via the underlying transport
     ///
     /// This event is emitted when the MQTT library needs to send a packet.
@@ -140,6 +244,16 @@ where
     /// * `PacketIdType` - The packet ID that has been released
     NotifyPacketIdReleased(PacketIdType),
 
+    /// Notification that the store has become empty
+    ///
+    /// This event is emitted when a received PUBACK, PUBCOMP, or PUBREC with an error
+    /// reason code erases the last packet held in the store, that is, when every stored
+    /// PUBLISH and PUBREL has been acknowledged, only if enabled by
+    /// `GenericConnection::set_notify_store_empty()`. Packets are stored only while
+    /// session state is kept. Applications can use it to wait for all messages to be
+    /// delivered before closing the connection.
+    NotifyStoreEmpty,
+
     /// Request to reset or start a timer
     ///
     /// This event is emitted when the MQTT library needs to set up a timer for
@@ -184,6 +298,30 @@ where
     /// * `MqttError` - The error that occurred
     NotifyError(MqttError),
 
+    /// Notification of a protocol irregularity that does not affect the connection
+    ///
+    /// This event is emitted when a received packet deviates from the specification
+    /// in a way that the library tolerates, for example a DISCONNECT whose reason code
+    /// is not allowed for the sender's role, only if enabled by
+    /// `GenericConnection::set_notify_warning()`. Unlike `NotifyError`, the packet is
+    /// processed as usual and no `RequestClose` is emitted because of it.
+    ///
+    /// # Parameters
+    ///
+    /// * `MqttError` - The detected irregularity
+    NotifyWarning(MqttError),
+
+    /// Notification of the received packet that caused the preceding `NotifyError`
+    ///
+    /// This event is emitted right after `NotifyError` for errors detected while
+    /// processing a received packet, only if enabled by
+    /// `GenericConnection::set_error_context()`.
+    ///
+    /// # Parameters
+    ///
+    /// * `MqttErrorContext` - The error with the type and packet identifier of the packet
+    NotifyErrorContext(MqttErrorContext),
+
     /// Request to close the connection
     ///
     /// This event is emitted when the MQTT library determines that the
@@ -223,6 +361,16 @@ where
                 state.serialize_field("packet", packet)?;
                 state.end()
             }
+            GenericEvent::NotifyPacketReceivedEx {
+                packet,
+                auto_responded,
+            } => {
+                let mut state = serializer.serialize_struct("GenericEvent", 3)?;
+                state.serialize_field("type", "notify_packet_received_ex")?;
+                state.serialize_field("packet", packet)?;
+                state.serialize_field("auto_responded", auto_responded)?;
+                state.end()
+            }
             GenericEvent::RequestSendPacket {
                 packet,
                 release_packet_id_if_send_error,
@@ -242,6 +390,11 @@ where
                 state.serialize_field("packet_id", packet_id)?;
                 state.end()
             }
+            GenericEvent::NotifyStoreEmpty => {
+                let mut state = serializer.serialize_struct("GenericEvent", 1)?;
+                state.serialize_field("type", "notify_store_empty")?;
+                state.end()
+            }
             GenericEvent::RequestTimerReset { kind, duration_ms } => {
                 let mut state = serializer.serialize_struct("GenericEvent", 3)?;
                 state.serialize_field("type", "request_timer_reset")?;
@@ -261,6 +414,20 @@ where
                 state.serialize_field("error", &format!("{error:?}"))?;
                 state.end()
             }
+            GenericEvent::NotifyWarning(error) => {
+                let mut state = serializer.serialize_struct("GenericEvent", 2)?;
+                state.serialize_field("type", "notify_warning")?;
+                state.serialize_field("error", &format!("{error:?}"))?;
+                state.end()
+            }
+            GenericEvent::NotifyErrorContext(context) => {
+                let mut state = serializer.serialize_struct("GenericEvent", 4)?;
+                state.serialize_field("type", "notify_error_context")?;
+                state.serialize_field("error", &format!("{:?}", context.error))?;
+                state.serialize_field("packet_type", &context.packet_type)?;
+                state.serialize_field("packet_id", &context.packet_id)?;
+                state.end()
+            }
             GenericEvent::RequestClose => {
                 let mut state = serializer.serialize_struct("GenericEvent", 1)?;
                 state.serialize_field("type", "request_close")?;
@@ -30,10 +30,15 @@ pub use packet_id_manager::PacketIdManager;
 pub mod core;
 pub use self::core::Connection;
 pub use self::core::GenericConnection;
+pub use self::core::LimitStatus;
+pub use self::core::{MQTT_MAX_PACKET_SIZE, MQTT_MAX_REMAINING_LENGTH};
 
 pub mod event;
+pub use self::event::Direction;
+pub use self::event::DisconnectCause;
 pub use self::event::Event;
 pub use self::event::GenericEvent;
+pub use self::event::MqttErrorContext;
 pub use self::event::TimerKind;
 
 mod packet_builder;
@@ -24,13 +24,17 @@ use crate::mqtt::common::{Arc, Cursor};
 use crate::mqtt::result_code::MqttError;
 use alloc::vec::Vec;
 
+/// Variable header and payload bytes of a received packet
 #[derive(Debug, Clone)]
 pub enum PacketData {
+    /// Bytes of any packet other than PUBLISH
     Normal(Vec<u8>),
+    /// Bytes of a PUBLISH packet, shared so that the payload can be referenced without copying
     Publish(Arc<[u8]>),
 }
 
 impl PacketData {
+    /// Get the bytes as a slice
     pub fn as_slice(&self) -> &[u8] {
         match self {
             PacketData::Normal(vec) => vec.as_slice(),
@@ -38,6 +42,7 @@ impl PacketData {
         }
     }
 
+    /// Get the number of bytes
     pub fn len(&self) -> u32 {
         match self {
             PacketData::Normal(vec) => vec.len().try_into().unwrap(),
@@ -45,11 +50,16 @@ impl PacketData {
         }
     }
 
+    /// Check if there are no bytes
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 }
 
+/// Complete packet assembled by `PacketBuilder`
+///
+/// Holds the fixed header byte and the bytes following the remaining length field.
+/// The bytes can be passed to the `parse()` function of the corresponding packet type.
 #[derive(Debug, Clone)]
 pub struct RawPacket {
     fixed_header: u8,
@@ -57,22 +67,27 @@ pub struct RawPacket {
 }
 
 impl RawPacket {
+    /// Get the bytes following the remaining length field
     pub fn data_as_slice(&self) -> &[u8] {
         self.data.as_slice()
     }
 
+    /// Get the packet type (upper 4 bits of the fixed header)
     pub fn packet_type(&self) -> u8 {
         self.fixed_header >> 4
     }
 
+    /// Get the flags (lower 4 bits of the fixed header)
     pub fn flags(&self) -> u8 {
         self.fixed_header & 0x0F
     }
 
+    /// Check if the packet is PUBLISH
     pub fn is_publish(&self) -> bool {
         self.packet_type() == 3
     }
 
+    /// Get the remaining length
     pub fn remaining_length(&self) -> u32 {
         self.data.len()
     }
@@ -90,6 +105,10 @@ pub enum PacketBuildResult {
 }
 
 /// Builder for constructing MQTT packet byte sequences
+///
+/// Assembles complete packets from arbitrarily fragmented input. It is used
+/// internally by `GenericConnection` and can also be used standalone, e.g. for
+/// custom framing or multiplexing without a connection.
 pub struct PacketBuilder {
     /// Current read state
     state: ReadState,
@@ -187,6 +206,12 @@ impl PacketBuilder {
                         return PacketBuildResult::Error(MqttError::MalformedPacket);
                     }
 
+                    // Non-minimal encoding check (e.g. 0x80 0x00 for 0)
+                    if self.multiplier != 1 && encoded_byte == 0 {
+                        self.reset();
+                        return PacketBuildResult::Error(MqttError::MalformedPacket);
+                    }
+
                     self.remaining_length +=
                         ((encoded_byte & 0x7F) as usize) * (self.multiplier as usize);
                     self.multiplier *= 128;
@@ -20,6 +20,8 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use alloc::vec::Vec;
+
 use crate::mqtt::packet::IsPacketId;
 use crate::mqtt::result_code::MqttError;
 use crate::mqtt::ValueAllocator;
@@ -50,6 +52,12 @@ where
             .ok_or(MqttError::PacketIdentifierFullyUsed)
     }
 
+    /// Get the packet ID that `acquire_unique_id()` would return next without acquiring it.
+    /// Returns `None` if no IDs are available.
+    pub fn peek_next_id(&self) -> Option<T> {
+        self.allocator.first_vacant()
+    }
+
     /// Register a packet ID externally acquired or reused.
     /// Returns `Ok(())` if successful, `Err(MqttError)` if the ID is already in use.
     pub fn register_id(&mut self, packet_id: T) -> Result<(), MqttError> {
@@ -69,6 +77,30 @@ where
         self.allocator.deallocate(packet_id);
     }
 
+    /// Export the packet IDs in use as contiguous inclusive ranges in ascending order.
+    /// This is more compact than the full set of IDs when persisting the state.
+    pub fn export_ranges(&self) -> Vec<(T, T)> {
+        self.allocator.used_ranges()
+    }
+
+    /// Replace the packet IDs in use with the ranges exported by `export_ranges()`.
+    /// Returns `Err(MqttError::PacketIdentifierInvalid)` if a range is empty or contains 0,
+    /// or `Err(MqttError::PacketIdentifierConflict)` if ranges overlap.
+    /// The state is unchanged on error.
+    pub fn import_ranges(&mut self, ranges: &[(T, T)]) -> Result<(), MqttError> {
+        let mut allocator = ValueAllocator::new(T::one(), T::max_value());
+        for &(low, high) in ranges {
+            if high < low || low < T::one() {
+                return Err(MqttError::PacketIdentifierInvalid);
+            }
+            if !allocator.use_range(low, high) {
+                return Err(MqttError::PacketIdentifierConflict);
+            }
+        }
+        self.allocator = allocator;
+        Ok(())
+    }
+
     /// Clear all state: all packet IDs become available again.
     pub fn clear(&mut self) {
         self.allocator.clear();
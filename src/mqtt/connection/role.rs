@@ -216,3 +216,16 @@ impl RoleType for Server {
 impl RoleType for Any {
     const IS_ANY: bool = true;
 }
+
+/// Runtime representation of the role a connection acts as
+///
+/// Returned by `GenericConnection::inferred_role()`. This is mainly useful for
+/// `Any` connections whose role is only known after the handshake has started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectionRole {
+    /// The connection acts as an MQTT client
+    Client,
+    /// The connection acts as an MQTT server (broker)
+    Server,
+}
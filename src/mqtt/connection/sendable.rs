@@ -426,7 +426,7 @@ where
         self,
         connection: &mut GenericConnection<role::Client, PacketIdType>,
     ) -> Vec<GenericEvent<PacketIdType>> {
-        connection.send(self)
+        connection.process_send_transformed(self)
     }
 }
 
@@ -438,7 +438,7 @@ where
         self,
         connection: &mut GenericConnection<role::Server, PacketIdType>,
     ) -> Vec<GenericEvent<PacketIdType>> {
-        connection.send(self)
+        connection.process_send_transformed(self)
     }
 }
 
@@ -450,6 +450,6 @@ where
         self,
         connection: &mut GenericConnection<role::Any, PacketIdType>,
     ) -> Vec<GenericEvent<PacketIdType>> {
-        connection.send(self)
+        connection.process_send_transformed(self)
     }
 }
@@ -38,6 +38,9 @@ macro_rules! impl_sendable_helper {
                 self,
                 connection: &mut GenericConnection<$role, PacketIdType>,
             ) -> Vec<GenericEvent<PacketIdType>> {
+                if connection.has_outgoing_transform() {
+                    return connection.process_send_transformed(self.into());
+                }
                 connection.$process_method(self)
             }
         }
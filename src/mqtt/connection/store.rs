@@ -22,6 +22,7 @@
 
 use crate::mqtt::common::tracing::trace;
 use crate::mqtt::common::IndexMap;
+use crate::mqtt::packet::GenericPacketTrait;
 use crate::mqtt::packet::GenericStorePacket;
 use crate::mqtt::packet::IsPacketId;
 use crate::mqtt::packet::ResponsePacket;
@@ -43,6 +44,16 @@ impl<PacketIdType: IsPacketId> GenericStore<PacketIdType> {
         }
     }
 
+    /// Get the number of stored packets.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
     /// Add a packet to the store.
     /// Returns true if inserted, false if a packet with same id already exists.
     pub fn add(&mut self, packet: GenericStorePacket<PacketIdType>) -> Result<(), MqttError> {
@@ -106,6 +117,16 @@ impl<PacketIdType: IsPacketId> GenericStore<PacketIdType> {
         }
     }
 
+    /// Return a reference to the stored packet with the given packet id, if any.
+    pub fn get(&self, packet_id: PacketIdType) -> Option<&GenericStorePacket<PacketIdType>> {
+        self.map.get(&packet_id)
+    }
+
+    /// Return the size in bytes of the largest stored packet, or 0 if the store is empty.
+    pub fn max_packet_size(&self) -> usize {
+        self.map.values().map(|pkt| pkt.size()).max().unwrap_or(0)
+    }
+
     /// Return a vector of all stored packets in insertion order.
     pub fn get_stored(&self) -> Vec<GenericStorePacket<PacketIdType>> {
         self.map.values().cloned().collect()
@@ -0,0 +1,81 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::mqtt::packet::GenericPacketTrait;
+use crate::mqtt::result_code::MqttError;
+
+/// Size-checked building for packet builders
+///
+/// Implemented by every packet builder. The provided [`build_within`](Self::build_within)
+/// builds the packet and rejects it if its encoded size exceeds a limit, such as
+/// the MaximumPacketSize announced by the peer.
+pub trait BuildWithin: Sized {
+    /// The packet type produced by the builder
+    type Packet: GenericPacketTrait;
+
+    /// Builds the packet
+    ///
+    /// Equivalent to the builder's inherent `build()`.
+    fn build_packet(self) -> Result<Self::Packet, MqttError>;
+
+    /// Builds the packet and checks that its encoded size fits within `max_size`
+    ///
+    /// This behaves like `build()`, but additionally rejects packets whose total
+    /// encoded size (fixed header included) exceeds `max_size`. It allows
+    /// oversized packets to be detected at construction time instead of when
+    /// they are sent.
+    ///
+    /// # Parameters
+    ///
+    /// * `max_size` - Maximum allowed encoded packet size in bytes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Self::Packet)` - Successfully built packet within the limit
+    /// * `Err(MqttError::PacketTooLarge)` - If the encoded size exceeds `max_size`
+    /// * `Err(MqttError)` - If `build()` fails for any other reason
+    fn build_within(self, max_size: usize) -> Result<Self::Packet, MqttError> {
+        let packet = self.build_packet()?;
+        if packet.size() > max_size {
+            return Err(MqttError::PacketTooLarge);
+        }
+        Ok(packet)
+    }
+}
+
+/// Implements [`BuildWithin`] for builders by forwarding to their inherent `build()`
+macro_rules! impl_build_within {
+    ($($builder:ident $(<$id:ident>)? => $packet:ident),* $(,)?) => {
+        $(
+            impl$(<$id: crate::mqtt::packet::IsPacketId>)? crate::mqtt::packet::BuildWithin
+                for $builder$(<$id>)?
+            {
+                type Packet = $packet$(<$id>)?;
+
+                fn build_packet(self) -> Result<Self::Packet, crate::mqtt::result_code::MqttError> {
+                    self.build()
+                }
+            }
+        )*
+    };
+}
+pub(crate) use impl_build_within;
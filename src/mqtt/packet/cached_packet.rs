@@ -0,0 +1,103 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use alloc::vec::Vec;
+
+use crate::mqtt::packet::GenericPacketTrait;
+
+/// Packet wrapper that caches its encoded wire bytes
+///
+/// Packets that are sent repeatedly without modification (e.g. a fixed PINGREQ or
+/// a re-published retained message) can be wrapped to avoid encoding them on every
+/// send. The bytes are computed lazily on the first call to
+/// `to_continuous_buffer_cached()` and reused until the packet is mutated through
+/// `packet_mut()`, which invalidates the cache.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mqtt_protocol_core::mqtt;
+///
+/// let mut pingreq = mqtt::packet::CachedPacket::new(mqtt::packet::v5_0::Pingreq::new());
+/// let bytes = pingreq.to_continuous_buffer_cached().to_vec();
+/// assert_eq!(pingreq.to_continuous_buffer_cached(), bytes.as_slice());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedPacket<P> {
+    packet: P,
+    buffer: Option<Vec<u8>>,
+}
+
+impl<P> CachedPacket<P>
+where
+    P: GenericPacketTrait,
+{
+    /// Create a new wrapper. The packet is not encoded until it is first requested.
+    pub fn new(packet: P) -> Self {
+        Self {
+            packet,
+            buffer: None,
+        }
+    }
+
+    /// Get the wrapped packet
+    pub fn packet(&self) -> &P {
+        &self.packet
+    }
+
+    /// Get the wrapped packet for modification
+    ///
+    /// The cached bytes are invalidated and re-encoded on the next
+    /// `to_continuous_buffer_cached()` call.
+    pub fn packet_mut(&mut self) -> &mut P {
+        self.buffer = None;
+        &mut self.packet
+    }
+
+    /// Unwrap the packet, discarding the cached bytes
+    pub fn into_inner(self) -> P {
+        self.packet
+    }
+
+    /// Check if the encoded bytes are currently cached
+    pub fn is_cached(&self) -> bool {
+        self.buffer.is_some()
+    }
+
+    /// Get the encoded packet bytes, encoding only if they are not cached yet
+    ///
+    /// The returned bytes are identical to `to_continuous_buffer()` of the wrapped packet.
+    pub fn to_continuous_buffer_cached(&mut self) -> &[u8] {
+        let packet = &self.packet;
+        self.buffer
+            .get_or_insert_with(|| packet.to_continuous_buffer())
+            .as_slice()
+    }
+}
+
+impl<P> From<P> for CachedPacket<P>
+where
+    P: GenericPacketTrait,
+{
+    fn from(packet: P) -> Self {
+        Self::new(packet)
+    }
+}
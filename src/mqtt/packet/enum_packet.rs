@@ -47,6 +47,24 @@ pub trait GenericPacketTrait {
     /// A vector containing the complete packet data
     fn to_continuous_buffer(&self) -> Vec<u8>;
 
+    /// Get the flags of the fixed header
+    ///
+    /// Returns the lower 4 bits of the first byte of the fixed header as they
+    /// are encoded on the wire. For PUBLISH these carry DUP, QoS, and RETAIN;
+    /// for PUBREL, SUBSCRIBE, and UNSUBSCRIBE they are the fixed `0b0010`;
+    /// for the other packet types they are `0b0000`.
+    /// This is useful for debugging and proxying.
+    ///
+    /// The default implementation reads the flags from `to_continuous_buffer()`.
+    /// The packet types of this crate override it without encoding the packet.
+    ///
+    /// # Returns
+    ///
+    /// The fixed header flags in the range `0x00..=0x0f`
+    fn raw_flags(&self) -> u8 {
+        self.to_continuous_buffer()[0] & 0x0f
+    }
+
     #[cfg(feature = "std")]
     fn to_buffers(&self) -> Vec<IoSlice<'_>>;
 }
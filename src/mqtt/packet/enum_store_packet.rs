@@ -87,6 +87,15 @@ where
         }
     }
 
+    fn raw_flags(&self) -> u8 {
+        match self {
+            GenericStorePacket::V3_1_1Publish(p) => p.raw_flags(),
+            GenericStorePacket::V3_1_1Pubrel(p) => p.raw_flags(),
+            GenericStorePacket::V5_0Publish(p) => p.raw_flags(),
+            GenericStorePacket::V5_0Pubrel(p) => p.raw_flags(),
+        }
+    }
+
     fn to_continuous_buffer(&self) -> Vec<u8> {
         match self {
             GenericStorePacket::V3_1_1Publish(p) => p.to_continuous_buffer(),
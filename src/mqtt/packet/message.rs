@@ -0,0 +1,64 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::string::String;
+
+use crate::mqtt::common::ArcPayload;
+use crate::mqtt::packet::Properties;
+use crate::mqtt::packet::Qos;
+
+/// Version-agnostic application message
+///
+/// An owned representation of the application message carried by a PUBLISH packet,
+/// for consumers that do not care about the MQTT version. It is created by
+/// `into_message()` of `v3_1_1::GenericPublish` and `v5_0::GenericPublish`.
+/// A message converted from an MQTT v3.1.1 PUBLISH has empty properties.
+///
+/// # Examples
+///
+/// ```ignore
+/// use mqtt_protocol_core::mqtt;
+///
+/// let publish = mqtt::packet::v3_1_1::Publish::builder()
+///     .topic_name("sensors/temperature")
+///     .unwrap()
+///     .payload(b"23.5")
+///     .build()
+///     .unwrap();
+///
+/// let message = publish.into_message();
+/// assert_eq!(message.topic_name, "sensors/temperature");
+/// assert!(message.props.is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Topic name of the message
+    pub topic_name: String,
+    /// Application message payload
+    pub payload: ArcPayload,
+    /// QoS level the message was published with
+    pub qos: Qos,
+    /// Retain flag of the message
+    pub retain: bool,
+    /// PUBLISH properties, empty for MQTT v3.1.1
+    pub props: Properties,
+}
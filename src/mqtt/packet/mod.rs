@@ -24,6 +24,9 @@ pub use self::mqtt_string::MqttString;
 mod mqtt_binary;
 pub use self::mqtt_binary::MqttBinary;
 
+mod build_within;
+pub(crate) use self::build_within::impl_build_within;
+pub use self::build_within::BuildWithin;
 mod enum_packet;
 mod enum_store_packet;
 mod property;
@@ -33,6 +36,8 @@ mod retain_handling;
 pub use self::retain_handling::RetainHandling;
 mod sub_entry;
 pub use self::sub_entry::{SubEntry, SubOpts};
+mod message;
+pub use self::message::Message;
 mod variable_byte_integer;
 pub use self::variable_byte_integer::{DecodeResult, VariableByteInteger};
 mod packet_type;
@@ -43,6 +48,8 @@ pub mod v3_1_1;
 pub mod v5_0;
 pub use self::enum_packet::{GenericPacket, GenericPacketDisplay, GenericPacketTrait, Packet};
 pub use self::enum_store_packet::{GenericStorePacket, ResponsePacket, StorePacket};
+mod cached_packet;
+pub use self::cached_packet::CachedPacket;
 pub use self::property::PayloadFormat;
 mod json_bin_encode;
 #[cfg(feature = "std")]
@@ -65,3 +72,6 @@ pub use self::topic_alias_recv::TopicAliasRecv;
 
 pub mod kind;
 pub mod prelude;
+
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
@@ -20,6 +20,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 pub use crate::mqtt::common::IntoPayload;
+pub use crate::mqtt::packet::build_within::BuildWithin;
 pub use crate::mqtt::packet::enum_packet::GenericPacketTrait;
 pub use crate::mqtt::packet::property::PropertiesSize;
 #[cfg(feature = "std")]
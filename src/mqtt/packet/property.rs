@@ -1425,6 +1425,10 @@ macro_rules! mqtt_property_variable_integer {
             pub fn parse(bytes: &[u8]) -> Result<(Self, usize), MqttError> {
                 match VariableByteInteger::decode_stream(bytes) {
                     DecodeResult::Ok(vbi, len) => {
+                        // Non-minimal encoding is malformed
+                        if len != vbi.size() {
+                            return Err(MqttError::MalformedPacket);
+                        }
                         if let Some(validator) = $validator {
                             validator(vbi.to_u32())?;
                         }
@@ -2435,10 +2439,8 @@ impl PropertiesParse for Properties {
             return Err(MqttError::MalformedPacket);
         }
 
-        let (prop_len, consumed) = match VariableByteInteger::decode_stream(data) {
-            DecodeResult::Ok(vbi, cons) => (vbi, cons),
-            _ => return Err(MqttError::MalformedPacket),
-        };
+        let (prop_len, consumed) =
+            VariableByteInteger::decode_strict(data).map_err(|_| MqttError::MalformedPacket)?;
 
         let mut cursor = consumed;
         let mut props = Properties::new();
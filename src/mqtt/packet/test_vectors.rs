@@ -0,0 +1,468 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Canonical wire-format test vectors
+//!
+//! This module provides representative packets of both MQTT v3.1.1 and v5.0
+//! together with their exact encoded byte sequences. The vectors are intended
+//! for interoperability and round-trip tests: each vector satisfies
+//! `parse(bytes) == packet` and `packet.to_continuous_buffer() == bytes`.
+//!
+//! The module is always compiled for the crate's own tests and is exposed
+//! publicly when the `test-vectors` feature is enabled.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::mqtt::common::{Arc, Cursor};
+use crate::mqtt::connection::{PacketBuildResult, PacketBuilder, Version};
+use crate::mqtt::packet::v3_1_1;
+use crate::mqtt::packet::v5_0;
+use crate::mqtt::packet::{
+    AuthenticationMethod, Packet, Qos, ReasonString, SubEntry, SubOpts, TopicAlias,
+};
+use crate::mqtt::result_code::{
+    AuthReasonCode, ConnectReasonCode, ConnectReturnCode, DisconnectReasonCode, MqttError,
+    PubackReasonCode, SubackReasonCode, SubackReturnCode, UnsubackReasonCode,
+};
+
+/// A single test vector: `(name, encoded bytes, expected packet)`
+pub type TestVector = (&'static str, Vec<u8>, Packet);
+
+/// Parse one complete packet from `bytes` for the given protocol version
+///
+/// The bytes must contain exactly one complete packet including the fixed header.
+///
+/// # Errors
+///
+/// * `MqttError::InsufficientBytes` - `bytes` does not contain a complete packet
+/// * `MqttError::MalformedPacket` - trailing bytes or an invalid packet type
+/// * Any error returned by the packet specific `parse()` function
+pub fn parse(version: Version, bytes: &[u8]) -> Result<Packet, MqttError> {
+    let mut cursor = Cursor::new(bytes);
+    let raw_packet = match PacketBuilder::new().feed(&mut cursor) {
+        PacketBuildResult::Complete(raw_packet) => raw_packet,
+        PacketBuildResult::Incomplete => return Err(MqttError::InsufficientBytes),
+        PacketBuildResult::Error(e) => return Err(e),
+    };
+    if cursor.position() as usize != bytes.len() {
+        return Err(MqttError::MalformedPacket);
+    }
+
+    let data = raw_packet.data_as_slice();
+    let packet: Packet = match (version, raw_packet.packet_type()) {
+        (Version::V3_1_1, 1) => v3_1_1::Connect::parse(data)?.0.into(),
+        (Version::V3_1_1, 2) => v3_1_1::Connack::parse(data)?.0.into(),
+        (Version::V3_1_1, 3) => v3_1_1::Publish::parse(raw_packet.flags(), Arc::from(data))?
+            .0
+            .into(),
+        (Version::V3_1_1, 4) => v3_1_1::Puback::parse(data)?.0.into(),
+        (Version::V3_1_1, 5) => v3_1_1::Pubrec::parse(data)?.0.into(),
+        (Version::V3_1_1, 6) => v3_1_1::Pubrel::parse(data)?.0.into(),
+        (Version::V3_1_1, 7) => v3_1_1::Pubcomp::parse(data)?.0.into(),
+        (Version::V3_1_1, 8) => v3_1_1::Subscribe::parse(data)?.0.into(),
+        (Version::V3_1_1, 9) => v3_1_1::Suback::parse(data)?.0.into(),
+        (Version::V3_1_1, 10) => v3_1_1::Unsubscribe::parse(data)?.0.into(),
+        (Version::V3_1_1, 11) => v3_1_1::Unsuback::parse(data)?.0.into(),
+        (Version::V3_1_1, 12) => v3_1_1::Pingreq::parse(data)?.0.into(),
+        (Version::V3_1_1, 13) => v3_1_1::Pingresp::parse(data)?.0.into(),
+        (Version::V3_1_1, 14) => v3_1_1::Disconnect::parse(data)?.0.into(),
+        (Version::V5_0, 1) => v5_0::Connect::parse(data)?.0.into(),
+        (Version::V5_0, 2) => v5_0::Connack::parse(data)?.0.into(),
+        (Version::V5_0, 3) => v5_0::Publish::parse(raw_packet.flags(), Arc::from(data))?
+            .0
+            .into(),
+        (Version::V5_0, 4) => v5_0::Puback::parse(data)?.0.into(),
+        (Version::V5_0, 5) => v5_0::Pubrec::parse(data)?.0.into(),
+        (Version::V5_0, 6) => v5_0::Pubrel::parse(data)?.0.into(),
+        (Version::V5_0, 7) => v5_0::Pubcomp::parse(data)?.0.into(),
+        (Version::V5_0, 8) => v5_0::Subscribe::parse(data)?.0.into(),
+        (Version::V5_0, 9) => v5_0::Suback::parse(data)?.0.into(),
+        (Version::V5_0, 10) => v5_0::Unsubscribe::parse(data)?.0.into(),
+        (Version::V5_0, 11) => v5_0::Unsuback::parse(data)?.0.into(),
+        (Version::V5_0, 12) => v5_0::Pingreq::parse(data)?.0.into(),
+        (Version::V5_0, 13) => v5_0::Pingresp::parse(data)?.0.into(),
+        (Version::V5_0, 14) => v5_0::Disconnect::parse(data)?.0.into(),
+        (Version::V5_0, 15) => v5_0::Auth::parse(data)?.0.into(),
+        _ => return Err(MqttError::MalformedPacket),
+    };
+    Ok(packet)
+}
+
+/// Return the representative test vectors for both protocol versions
+///
+/// The protocol version of each vector can be obtained from
+/// `packet.protocol_version()`.
+pub fn vectors() -> Vec<TestVector> {
+    let mut vectors = v3_1_1_vectors();
+    vectors.extend(v5_0_vectors());
+    vectors
+}
+
+fn v3_1_1_vectors() -> Vec<TestVector> {
+    vec![
+        (
+            "v3_1_1_connect",
+            vec![
+                0x10, 0x10, // fixed header
+                0x00, 0x04, b'M', b'Q', b'T', b'T', // protocol name
+                0x04, // protocol level
+                0x02, // connect flags (clean session)
+                0x00, 0x3c, // keep alive 60
+                0x00, 0x04, b'c', b'i', b'd', b'1', // client id
+            ],
+            v3_1_1::Connect::builder()
+                .client_id("cid1")
+                .unwrap()
+                .clean_session(true)
+                .keep_alive(60)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_connack",
+            vec![0x20, 0x02, 0x01, 0x00],
+            v3_1_1::Connack::builder()
+                .session_present(true)
+                .return_code(ConnectReturnCode::Accepted)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_publish_qos0",
+            vec![0x31, 0x05, 0x00, 0x01, b'a', b'h', b'i'],
+            v3_1_1::Publish::builder()
+                .topic_name("a")
+                .unwrap()
+                .retain(true)
+                .payload(b"hi")
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_publish_qos1",
+            vec![0x32, 0x07, 0x00, 0x01, b'a', 0x00, 0x01, b'h', b'i'],
+            v3_1_1::Publish::builder()
+                .topic_name("a")
+                .unwrap()
+                .qos(Qos::AtLeastOnce)
+                .packet_id(1)
+                .payload(b"hi")
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_puback",
+            vec![0x40, 0x02, 0x00, 0x01],
+            v3_1_1::Puback::builder()
+                .packet_id(1)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_pubrec",
+            vec![0x50, 0x02, 0x00, 0x02],
+            v3_1_1::Pubrec::builder()
+                .packet_id(2)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_pubrel",
+            vec![0x62, 0x02, 0x00, 0x02],
+            v3_1_1::Pubrel::builder()
+                .packet_id(2)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_pubcomp",
+            vec![0x70, 0x02, 0x00, 0x02],
+            v3_1_1::Pubcomp::builder()
+                .packet_id(2)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_subscribe",
+            vec![0x82, 0x08, 0x00, 0x03, 0x00, 0x03, b'a', b'/', b'#', 0x01],
+            v3_1_1::Subscribe::builder()
+                .packet_id(3)
+                .entries(vec![SubEntry::new(
+                    "a/#",
+                    SubOpts::new().set_qos(Qos::AtLeastOnce),
+                )
+                .unwrap()])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_suback",
+            vec![0x90, 0x04, 0x00, 0x03, 0x01, 0x80],
+            v3_1_1::Suback::builder()
+                .packet_id(3)
+                .return_codes(vec![
+                    SubackReturnCode::SuccessMaximumQos1,
+                    SubackReturnCode::Failure,
+                ])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_unsubscribe",
+            vec![0xa2, 0x07, 0x00, 0x04, 0x00, 0x03, b'a', b'/', b'#'],
+            v3_1_1::Unsubscribe::builder()
+                .packet_id(4)
+                .entries(vec!["a/#"])
+                .unwrap()
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_unsuback",
+            vec![0xb0, 0x02, 0x00, 0x04],
+            v3_1_1::Unsuback::builder()
+                .packet_id(4)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v3_1_1_pingreq",
+            vec![0xc0, 0x00],
+            v3_1_1::Pingreq::new().into(),
+        ),
+        (
+            "v3_1_1_pingresp",
+            vec![0xd0, 0x00],
+            v3_1_1::Pingresp::new().into(),
+        ),
+        (
+            "v3_1_1_disconnect",
+            vec![0xe0, 0x00],
+            v3_1_1::Disconnect::new().into(),
+        ),
+    ]
+}
+
+fn v5_0_vectors() -> Vec<TestVector> {
+    vec![
+        (
+            "v5_0_connect",
+            vec![
+                0x10, 0x11, // fixed header
+                0x00, 0x04, b'M', b'Q', b'T', b'T', // protocol name
+                0x05, // protocol level
+                0x02, // connect flags (clean start)
+                0x00, 0x3c, // keep alive 60
+                0x00, // property length
+                0x00, 0x04, b'c', b'i', b'd', b'1', // client id
+            ],
+            v5_0::Connect::builder()
+                .client_id("cid1")
+                .unwrap()
+                .clean_start(true)
+                .keep_alive(60)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_connack",
+            vec![0x20, 0x03, 0x00, 0x00, 0x00],
+            v5_0::Connack::builder()
+                .session_present(false)
+                .reason_code(ConnectReasonCode::Success)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_publish_qos1_topic_alias",
+            vec![
+                0x32, 0x0b, // fixed header
+                0x00, 0x01, b'a', // topic name
+                0x00, 0x01, // packet id
+                0x03, 0x23, 0x00, 0x01, // properties (TopicAlias 1)
+                b'h', b'i', // payload
+            ],
+            v5_0::Publish::builder()
+                .topic_name("a")
+                .unwrap()
+                .qos(Qos::AtLeastOnce)
+                .packet_id(1)
+                .props(vec![TopicAlias::new(1).unwrap().into()])
+                .payload(b"hi")
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_puback_minimal",
+            vec![0x40, 0x02, 0x00, 0x01],
+            v5_0::Puback::builder().packet_id(1).build().unwrap().into(),
+        ),
+        (
+            "v5_0_puback_reason_string",
+            vec![
+                0x40, 0x09, 0x00, 0x01, 0x80, 0x05, 0x1f, 0x00, 0x02, b'n', b'g',
+            ],
+            v5_0::Puback::builder()
+                .packet_id(1)
+                .reason_code(PubackReasonCode::UnspecifiedError)
+                .props(vec![ReasonString::new("ng").unwrap().into()])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_pubrel",
+            vec![0x62, 0x02, 0x00, 0x02],
+            v5_0::Pubrel::builder().packet_id(2).build().unwrap().into(),
+        ),
+        (
+            "v5_0_subscribe",
+            vec![
+                0x82, 0x09, 0x00, 0x03, 0x00, 0x00, 0x03, b'a', b'/', b'+', 0x02,
+            ],
+            v5_0::Subscribe::builder()
+                .packet_id(3)
+                .entries(vec![SubEntry::new(
+                    "a/+",
+                    SubOpts::new().set_qos(Qos::ExactlyOnce),
+                )
+                .unwrap()])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_suback",
+            vec![0x90, 0x04, 0x00, 0x03, 0x00, 0x02],
+            v5_0::Suback::builder()
+                .packet_id(3)
+                .reason_codes(vec![SubackReasonCode::GrantedQos2])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_unsubscribe",
+            vec![0xa2, 0x08, 0x00, 0x04, 0x00, 0x00, 0x03, b'a', b'/', b'+'],
+            v5_0::Unsubscribe::builder()
+                .packet_id(4)
+                .entries(vec!["a/+"])
+                .unwrap()
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_unsuback",
+            vec![0xb0, 0x04, 0x00, 0x04, 0x00, 0x11],
+            v5_0::Unsuback::builder()
+                .packet_id(4)
+                .reason_codes(vec![UnsubackReasonCode::NoSubscriptionExisted])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_pingreq",
+            vec![0xc0, 0x00],
+            v5_0::Pingreq::new().into(),
+        ),
+        (
+            "v5_0_pingresp",
+            vec![0xd0, 0x00],
+            v5_0::Pingresp::new().into(),
+        ),
+        (
+            "v5_0_disconnect",
+            vec![0xe0, 0x01, 0x8e],
+            v5_0::Disconnect::builder()
+                .reason_code(DisconnectReasonCode::SessionTakenOver)
+                .build()
+                .unwrap()
+                .into(),
+        ),
+        (
+            "v5_0_auth",
+            vec![0xf0, 0x08, 0x18, 0x06, 0x15, 0x00, 0x03, b'a', b'b', b'c'],
+            v5_0::Auth::builder()
+                .reason_code(AuthReasonCode::ContinueAuthentication)
+                .props(vec![AuthenticationMethod::new("abc").unwrap().into()])
+                .build()
+                .unwrap()
+                .into(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mqtt::packet::GenericPacketTrait;
+
+    #[test]
+    fn test_parse_encoded_packet_equals_expected() {
+        for (name, _bytes, packet) in vectors() {
+            let encoded = packet.to_continuous_buffer();
+            let parsed = parse(packet.protocol_version(), &encoded)
+                .unwrap_or_else(|e| panic!("{name}: parse failed: {e}"));
+            assert_eq!(parsed, packet, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_encode_parsed_bytes_equals_bytes() {
+        for (name, bytes, packet) in vectors() {
+            let parsed = parse(packet.protocol_version(), &bytes)
+                .unwrap_or_else(|e| panic!("{name}: parse failed: {e}"));
+            assert_eq!(parsed, packet, "{name}");
+            assert_eq!(parsed.to_continuous_buffer(), bytes, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_parse_incomplete_and_trailing_bytes() {
+        assert_eq!(
+            parse(Version::V3_1_1, &[0x40, 0x02, 0x00]),
+            Err(MqttError::InsufficientBytes)
+        );
+        assert_eq!(
+            parse(Version::V3_1_1, &[0xc0, 0x00, 0xc0]),
+            Err(MqttError::MalformedPacket)
+        );
+    }
+}
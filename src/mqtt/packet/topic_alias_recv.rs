@@ -101,6 +101,16 @@ impl TopicAliasRecv {
         self.aliases.clear();
     }
 
+    /// Get the number of registered topic-alias mappings
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// Check whether no topic-alias mapping is registered
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
     /// Get the maximum alias value
     pub fn max(&self) -> TopicAliasType {
         self.max_alias
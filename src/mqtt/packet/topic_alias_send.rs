@@ -191,6 +191,16 @@ impl TopicAliasSend {
             .unwrap_or(Self::MIN_ALIAS)
     }
 
+    /// Get the number of registered topic-alias mappings
+    pub fn len(&self) -> usize {
+        self.alias_to_topic.len()
+    }
+
+    /// Check whether no topic-alias mapping is registered
+    pub fn is_empty(&self) -> bool {
+        self.alias_to_topic.is_empty()
+    }
+
     /// Get the maximum alias value
     pub fn max(&self) -> TopicAliasType {
         self.max_alias
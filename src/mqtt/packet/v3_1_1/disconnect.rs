@@ -484,6 +484,10 @@ impl GenericPacketTrait for Disconnect {
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Implements the generic packet display trait for DISCONNECT packets
@@ -415,15 +415,11 @@ where
             return Err(MqttError::MalformedPacket);
         }
 
-        let all_zeros = &data[0..buffer_size].iter().all(|&b| b == 0);
-        if *all_zeros {
+        let packet_id = PacketIdType::from_buffer(&data[0..buffer_size]);
+        if packet_id.is_zero() {
             return Err(MqttError::MalformedPacket);
         }
-
-        let mut packet_id_buf = PacketIdType::Buffer::default();
-        packet_id_buf
-            .as_mut()
-            .copy_from_slice(&data[0..buffer_size]);
+        let packet_id_buf = packet_id.to_buffer();
         cursor += buffer_size;
 
         // reason_code
@@ -748,6 +744,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// `GenericPacketDisplay` implementation for `GenericPuback`.
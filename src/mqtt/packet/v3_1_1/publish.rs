@@ -39,6 +39,7 @@ use crate::mqtt::packet::qos::Qos;
 use crate::mqtt::packet::variable_byte_integer::VariableByteInteger;
 use crate::mqtt::packet::GenericPacketDisplay;
 use crate::mqtt::packet::GenericPacketTrait;
+use crate::mqtt::packet::Message;
 use crate::mqtt::packet::{IntoPacketId, IsPacketId};
 use crate::mqtt::result_code::MqttError;
 use crate::mqtt::{Arc, ArcPayload, IntoPayload};
@@ -461,6 +462,37 @@ where
         &self.payload_buf
     }
 
+    /// Converts the packet into a version-agnostic `Message`
+    ///
+    /// Consumes the packet and returns its topic name, payload, QoS, retain flag
+    /// and properties without copying the payload.
+    /// MQTT v3.1.1 has no properties, so the properties of the message are empty.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// let publish = mqtt::packet::v3_1_1::Publish::builder()
+    ///     .topic_name("greetings")
+    ///     .unwrap()
+    ///     .payload(b"hello")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let message = publish.into_message();
+    /// assert_eq!(message.payload.as_slice(), b"hello");
+    /// ```
+    pub fn into_message(self) -> Message {
+        Message {
+            topic_name: self.topic_name().into(),
+            qos: self.qos(),
+            retain: self.retain(),
+            payload: self.payload_buf,
+            props: Vec::new(),
+        }
+    }
+
     /// Returns the total size of the packet in bytes
     ///
     /// This includes the fixed header, variable header, and payload.
@@ -641,9 +673,11 @@ where
             if data_arc.len() < cursor + buffer_size {
                 return Err(MqttError::MalformedPacket);
             }
-            let mut buf = PacketIdType::Buffer::default();
-            buf.as_mut()
-                .copy_from_slice(&data_arc[cursor..cursor + buffer_size]);
+            let packet_id = PacketIdType::from_buffer(&data_arc[cursor..cursor + buffer_size]);
+            if packet_id.is_zero() {
+                return Err(MqttError::MalformedPacket);
+            }
+            let buf = packet_id.to_buffer();
             cursor += buffer_size;
             Some(buf)
         } else {
@@ -1196,6 +1230,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// GenericPacketDisplay implementation for PUBLISH packets
@@ -294,7 +294,8 @@ where
     /// # Errors
     ///
     /// * `MqttError::MalformedPacket` - If the packet structure is invalid
-    /// * `MqttError::ProtocolError` - If the packet violates MQTT protocol rules (e.g., no entries)
+    /// * `MqttError::ProtocolError` - If the packet violates MQTT protocol rules (e.g., no entries
+    ///   or a packet identifier of 0)
     ///
     /// # Examples
     ///
@@ -316,6 +317,9 @@ where
         }
 
         let packet_id = PacketIdType::from_buffer(&data[0..buffer_size]);
+        if packet_id.is_zero() {
+            return Err(MqttError::ProtocolError);
+        }
         let packet_id_buf = packet_id.to_buffer();
         cursor += buffer_size;
 
@@ -724,6 +728,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Generic packet display trait implementation for SUBSCRIBE packets
@@ -300,7 +300,7 @@ where
     ///
     /// Returns `MqttError` if:
     /// - The packet is malformed or incomplete
-    /// - The packet identifier is zero (invalid)
+    /// - The packet identifier is zero (protocol error)
     /// - No topic filter entries are present (protocol error)
     /// - UTF-8 decoding fails for topic filters
     ///
@@ -331,6 +331,9 @@ where
         }
 
         let packet_id = PacketIdType::from_buffer(&data[0..buffer_size]);
+        if packet_id.is_zero() {
+            return Err(MqttError::ProtocolError);
+        }
         let packet_id_buf = packet_id.to_buffer();
         cursor += buffer_size;
 
@@ -712,6 +715,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Generic packet display trait implementation for UNSUBSCRIBE packets
@@ -195,6 +195,52 @@ impl Auth {
             .and_then(|buf| AuthReasonCode::try_from(buf[0]).ok())
     }
 
+    /// Get the Authentication Method property
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&str)` - The authentication method if the property is present
+    /// * `None` - If the packet has no Authentication Method property
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// if let Some(method) = auth.authentication_method() {
+    ///     println!("method: {method}");
+    /// }
+    /// ```
+    pub fn authentication_method(&self) -> Option<&str> {
+        self.props.as_ref()?.iter().find_map(|prop| match prop {
+            Property::AuthenticationMethod(p) => Some(p.val()),
+            _ => None,
+        })
+    }
+
+    /// Get the Authentication Data property
+    ///
+    /// # Returns
+    ///
+    /// * `Some(&[u8])` - The authentication data if the property is present
+    /// * `None` - If the packet has no Authentication Data property
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// if let Some(data) = auth.authentication_data() {
+    ///     // compute the next authentication step
+    /// }
+    /// ```
+    pub fn authentication_data(&self) -> Option<&[u8]> {
+        self.props.as_ref()?.iter().find_map(|prop| match prop {
+            Property::AuthenticationData(p) => Some(p.val()),
+            _ => None,
+        })
+    }
+
     /// Calculate the total size of the AUTH packet in bytes
     ///
     /// Returns the complete size of the packet including the fixed header,
@@ -665,6 +711,10 @@ impl GenericPacketTrait for Auth {
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// GenericPacketDisplay implementation for AUTH packets
@@ -589,6 +589,10 @@ impl Connect {
         let connect_flags = data[cursor];
         let connect_flags_buf = [connect_flags];
         cursor += 1;
+        // Reserved flag must be zero
+        if (connect_flags & 0b0000_0001) != 0 {
+            return Err(MqttError::MalformedPacket);
+        }
 
         // Keep Alive
         if data.len() < cursor + 2 {
@@ -1296,6 +1300,10 @@ impl GenericPacketTrait for Connect {
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Implements generic packet display behavior for CONNECT packets
@@ -59,3 +59,30 @@ mod disconnect;
 pub use self::disconnect::Disconnect;
 mod auth;
 pub use self::auth::Auth;
+
+use self::{
+    auth::AuthBuilder, connack::ConnackBuilder, connect::ConnectBuilder,
+    disconnect::DisconnectBuilder, pingreq::PingreqBuilder, pingresp::PingrespBuilder,
+    puback::GenericPubackBuilder, pubcomp::GenericPubcompBuilder, publish::GenericPublishBuilder,
+    pubrec::GenericPubrecBuilder, pubrel::GenericPubrelBuilder, suback::GenericSubackBuilder,
+    subscribe::GenericSubscribeBuilder, unsuback::GenericUnsubackBuilder,
+    unsubscribe::GenericUnsubscribeBuilder,
+};
+
+crate::mqtt::packet::impl_build_within! {
+    ConnectBuilder => Connect,
+    ConnackBuilder => Connack,
+    GenericPublishBuilder<PacketIdType> => GenericPublish,
+    GenericPubackBuilder<PacketIdType> => GenericPuback,
+    GenericPubrecBuilder<PacketIdType> => GenericPubrec,
+    GenericPubrelBuilder<PacketIdType> => GenericPubrel,
+    GenericPubcompBuilder<PacketIdType> => GenericPubcomp,
+    GenericSubscribeBuilder<PacketIdType> => GenericSubscribe,
+    GenericSubackBuilder<PacketIdType> => GenericSuback,
+    GenericUnsubscribeBuilder<PacketIdType> => GenericUnsubscribe,
+    GenericUnsubackBuilder<PacketIdType> => GenericUnsuback,
+    PingreqBuilder => Pingreq,
+    PingrespBuilder => Pingresp,
+    DisconnectBuilder => Disconnect,
+    AuthBuilder => Auth,
+}
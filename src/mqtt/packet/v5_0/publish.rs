@@ -42,6 +42,7 @@ use crate::mqtt::packet::topic_alias_send::TopicAliasType;
 use crate::mqtt::packet::variable_byte_integer::VariableByteInteger;
 use crate::mqtt::packet::GenericPacketDisplay;
 use crate::mqtt::packet::GenericPacketTrait;
+use crate::mqtt::packet::Message;
 #[cfg(feature = "std")]
 use crate::mqtt::packet::PropertiesToBuffers;
 use crate::mqtt::packet::{IntoPacketId, IsPacketId};
@@ -223,6 +224,52 @@ where
         GenericPublishBuilder::<PacketIdType>::default()
     }
 
+    /// Creates a QoS 0 reply PUBLISH packet for a request/response flow
+    ///
+    /// The topic name of the reply is the ResponseTopic property of the request,
+    /// and the CorrelationData property of the request (if any) is copied to the reply.
+    /// Other properties of the request are not copied.
+    ///
+    /// # Parameters
+    ///
+    /// * `request` - The received request PUBLISH packet
+    /// * `payload` - The payload of the reply
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(GenericPublish)` - The reply PUBLISH packet
+    /// * `Err(MqttError::TopicNameInvalid)` - If the request has no ResponseTopic property
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// let reply = mqtt::packet::v5_0::Publish::reply_to(&request, b"result")?;
+    /// let events = connection.send(reply.into());
+    /// ```
+    pub fn reply_to<T>(request: &Self, payload: T) -> Result<Self, MqttError>
+    where
+        T: IntoPayload,
+    {
+        let mut response_topic = None;
+        let mut props = Properties::new();
+        for prop in &request.props {
+            match prop {
+                Property::ResponseTopic(p) => response_topic = Some(p.val()),
+                Property::CorrelationData(_) => props.push(prop.clone()),
+                _ => {}
+            }
+        }
+        let topic_name = response_topic.ok_or(MqttError::TopicNameInvalid)?;
+
+        GenericPublishBuilder::<PacketIdType>::default()
+            .topic_name(topic_name)?
+            .payload(payload)
+            .props(props)
+            .build()
+    }
+
     /// Returns the packet type for PUBLISH packets
     ///
     /// This is always `PacketType::Publish` for PUBLISH packet instances.
@@ -484,6 +531,68 @@ where
         &self.payload_buf
     }
 
+    /// Converts the packet into a version-agnostic `Message`
+    ///
+    /// Consumes the packet and returns its topic name, payload, QoS, retain flag
+    /// and properties without copying the payload.
+    /// For a received PUBLISH, the topic name restored from a topic alias by the
+    /// connection is used.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// let publish = mqtt::packet::v5_0::Publish::builder()
+    ///     .topic_name("greetings")
+    ///     .unwrap()
+    ///     .payload(b"hello")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let message = publish.into_message();
+    /// assert_eq!(message.payload.as_slice(), b"hello");
+    /// ```
+    pub fn into_message(self) -> Message {
+        Message {
+            topic_name: self.topic_name().into(),
+            qos: self.qos(),
+            retain: self.retain(),
+            payload: self.payload_buf,
+            props: self.props,
+        }
+    }
+
+    /// Returns all SubscriptionIdentifier property values
+    ///
+    /// A PUBLISH delivered by the server contains one SubscriptionIdentifier property
+    /// for each matching subscription that has a subscription identifier. Clients can
+    /// use them to route the message to the right handler.
+    ///
+    /// # Returns
+    ///
+    /// The subscription identifiers in the order they appear in the properties.
+    /// Empty if the packet has no SubscriptionIdentifier property.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use mqtt_protocol_core::mqtt;
+    ///
+    /// for id in publish.subscription_identifiers() {
+    ///     // dispatch to the handler registered for id
+    /// }
+    /// ```
+    pub fn subscription_identifiers(&self) -> Vec<u32> {
+        self.props
+            .iter()
+            .filter_map(|prop| match prop {
+                Property::SubscriptionIdentifier(p) => Some(p.val()),
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Remove TopicAlias property and add topic name
     ///
     /// This method is used for store regulation - it sets the topic name and removes
@@ -980,9 +1089,11 @@ where
             if data_arc.len() < cursor + buffer_size {
                 return Err(MqttError::MalformedPacket);
             }
-            let mut buf = PacketIdType::Buffer::default();
-            buf.as_mut()
-                .copy_from_slice(&data_arc[cursor..cursor + buffer_size]);
+            let packet_id = PacketIdType::from_buffer(&data_arc[cursor..cursor + buffer_size]);
+            if packet_id.is_zero() {
+                return Err(MqttError::MalformedPacket);
+            }
+            let buf = packet_id.to_buffer();
             cursor += buffer_size;
             Some(buf)
         } else {
@@ -1535,6 +1646,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Generic packet display trait implementation for PUBLISH packets
@@ -314,7 +314,8 @@ where
     /// # Errors
     ///
     /// * `MqttError::MalformedPacket` - If the packet structure is invalid
-    /// * `MqttError::ProtocolError` - If the packet violates MQTT protocol rules (e.g., no entries)
+    /// * `MqttError::ProtocolError` - If the packet violates MQTT protocol rules (e.g., no entries
+    ///   or a packet identifier of 0)
     ///
     /// # Examples
     ///
@@ -336,6 +337,9 @@ where
         }
 
         let packet_id = PacketIdType::from_buffer(&data[0..buffer_size]);
+        if packet_id.is_zero() {
+            return Err(MqttError::ProtocolError);
+        }
         let packet_id_buf = packet_id.to_buffer();
         cursor += buffer_size;
 
@@ -819,6 +823,10 @@ where
     fn to_continuous_buffer(&self) -> Vec<u8> {
         self.to_continuous_buffer()
     }
+
+    fn raw_flags(&self) -> u8 {
+        self.fixed_header[0] & 0b0000_1111
+    }
 }
 
 /// Generic packet display trait implementation for SUBSCRIBE packets
@@ -842,10 +850,11 @@ where
 /// Validates properties for SUBSCRIBE packets according to MQTT 5.0 specification
 ///
 /// Only certain properties are allowed in SUBSCRIBE packets:
-/// - Subscription Identifier: Associates a numeric identifier with the subscription
+/// - Subscription Identifier: Associates a numeric identifier with the subscription (max 1)
 /// - User Properties: Application-specific key-value pairs
 ///
-/// Any other properties will result in a protocol error.
+/// Any other properties, or more than one Subscription Identifier, will result
+/// in a protocol error.
 ///
 /// # Parameters
 ///
@@ -854,7 +863,7 @@ where
 /// # Returns
 ///
 /// * `Ok(())` - If all properties are valid for SUBSCRIBE packets
-/// * `Err(MqttError::ProtocolError)` - If invalid properties are found
+/// * `Err(MqttError::ProtocolError)` - If invalid or duplicated properties are found
 ///
 /// # Examples
 ///
@@ -867,12 +876,16 @@ where
 /// // This would be valid for SUBSCRIBE packets
 /// ```
 fn validate_subscribe_properties(props: &Properties) -> Result<(), MqttError> {
+    let mut count_subscription_identifier = 0;
     for prop in props {
         match prop {
-            Property::SubscriptionIdentifier(_) => {}
+            Property::SubscriptionIdentifier(_) => count_subscription_identifier += 1,
             Property::UserProperty(_) => {}
             _ => return Err(MqttError::ProtocolError),
         }
     }
+    if count_subscription_identifier > 1 {
+        return Err(MqttError::ProtocolError);
+    }
     Ok(())
 }
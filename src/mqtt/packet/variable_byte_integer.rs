@@ -20,6 +20,7 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::mqtt::result_code::MqttError;
 use alloc::vec::Vec;
 use arrayvec::ArrayVec;
 use core::convert::{From, TryFrom};
@@ -126,6 +127,40 @@ impl VariableByteInteger {
             DecodeResult::Err("Malformed VariableByteInteger: too many bytes")
         }
     }
+
+    /// Decode with `MqttError` results: if enough bytes, returns `(vbi, consumed)`.
+    ///
+    /// Like `decode_stream()`, non-minimal (overlong) encodings such as `0x80 0x00`
+    /// for the value 0 are accepted. Use `decode_strict()` to reject them.
+    ///
+    /// # Errors
+    ///
+    /// * `MqttError::InsufficientBytes` - If `buf` ends before the encoding terminates
+    /// * `MqttError::MalformedPacket` - If the encoding is invalid
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), MqttError> {
+        match Self::decode_stream(buf) {
+            DecodeResult::Ok(vbi, consumed) => Ok((vbi, consumed)),
+            DecodeResult::Incomplete => Err(MqttError::InsufficientBytes),
+            DecodeResult::Err(_) => Err(MqttError::MalformedPacket),
+        }
+    }
+
+    /// Decode rejecting non-minimal encodings: if enough bytes, returns `(vbi, consumed)`.
+    ///
+    /// The MQTT specification requires the minimum number of bytes, so overlong
+    /// encodings such as `0x80 0x00` for the value 0 are malformed.
+    ///
+    /// # Errors
+    ///
+    /// * `MqttError::InsufficientBytes` - If `buf` ends before the encoding terminates
+    /// * `MqttError::MalformedPacket` - If the encoding is invalid or overlong
+    pub fn decode_strict(buf: &[u8]) -> Result<(Self, usize), MqttError> {
+        let (vbi, consumed) = Self::decode(buf)?;
+        if consumed != vbi.size() {
+            return Err(MqttError::MalformedPacket);
+        }
+        Ok((vbi, consumed))
+    }
 }
 
 /// Result for streaming decode.
@@ -81,6 +81,11 @@ pub enum MqttError {
     PacketProcessFailed = 0x018B,
     ValueOutOfRange = 0x018C,
     InvalidQos = 0x018D,
+    AckQosMismatch = 0x018E,
+    DisconnectReasonCodeNotAllowed = 0x018F,
+    KeepAliveBelowMinimum = 0x0190,
+    OptionalPropertiesTooLarge = 0x0191,
+    PubrecNotReceived = 0x0192,
 }
 
 impl core::error::Error for MqttError {}
@@ -154,6 +159,11 @@ impl core::fmt::Display for MqttError {
             Self::PacketProcessFailed => "PacketProcessFailed",
             Self::ValueOutOfRange => "ValueOutOfRange",
             Self::InvalidQos => "InvalidQos",
+            Self::AckQosMismatch => "AckQosMismatch",
+            Self::DisconnectReasonCodeNotAllowed => "DisconnectReasonCodeNotAllowed",
+            Self::KeepAliveBelowMinimum => "KeepAliveBelowMinimum",
+            Self::OptionalPropertiesTooLarge => "OptionalPropertiesTooLarge",
+            Self::PubrecNotReceived => "PubrecNotReceived",
         };
         write!(f, "{s}")
     }
@@ -410,6 +420,35 @@ pub enum DisconnectReasonCode {
     WildcardSubscriptionsNotSupported = 0xa2,
 }
 
+impl DisconnectReasonCode {
+    /// Check whether a client is allowed to send DISCONNECT with this reason code
+    pub fn is_sendable_by_client(&self) -> bool {
+        !matches!(
+            self,
+            Self::NotAuthorized
+                | Self::ServerBusy
+                | Self::ServerShuttingDown
+                | Self::KeepAliveTimeout
+                | Self::SessionTakenOver
+                | Self::TopicFilterInvalid
+                | Self::RetainNotSupported
+                | Self::QosNotSupported
+                | Self::UseAnotherServer
+                | Self::ServerMoved
+                | Self::SharedSubscriptionsNotSupported
+                | Self::ConnectionRateExceeded
+                | Self::MaximumConnectTime
+                | Self::SubscriptionIdentifiersNotSupported
+                | Self::WildcardSubscriptionsNotSupported
+        )
+    }
+
+    /// Check whether a server is allowed to send DISCONNECT with this reason code
+    pub fn is_sendable_by_server(&self) -> bool {
+        !matches!(self, Self::DisconnectWithWillMessage)
+    }
+}
+
 impl core::fmt::Display for DisconnectReasonCode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let s = match self {
@@ -501,6 +540,12 @@ impl From<MqttError> for DisconnectReasonCode {
             MqttError::WildcardSubscriptionsNotSupported => {
                 DisconnectReasonCode::WildcardSubscriptionsNotSupported
             }
+            // An acknowledgement for the wrong QoS flow is a protocol violation
+            MqttError::AckQosMismatch => DisconnectReasonCode::ProtocolError,
+            // A reason code the sender's role must not use is a protocol violation
+            MqttError::DisconnectReasonCodeNotAllowed => DisconnectReasonCode::ProtocolError,
+            // Oversized optional properties make the whole packet too large
+            MqttError::OptionalPropertiesTooLarge => DisconnectReasonCode::PacketTooLarge,
             // All other MqttError variants map to UnspecifiedError
             _ => DisconnectReasonCode::UnspecifiedError,
         }
@@ -227,6 +227,19 @@ fn signed_value() {
     assert_eq!(a.interval_count(), 3);
 }
 
+#[test]
+fn is_used_out_of_range() {
+    common::init_tracing();
+    let mut a = ValueAllocator::new(1u16, u16::MAX);
+    assert!(!a.is_used(0));
+    assert!(!a.is_used(1));
+    assert_eq!(a.allocate(), Some(1));
+    assert!(a.is_used(1));
+    assert!(!a.is_used(0));
+    assert!(a.use_value(u16::MAX));
+    assert!(a.is_used(u16::MAX));
+}
+
 #[test]
 fn dump() {
     common::init_tracing();
@@ -264,3 +277,33 @@ fn dump() {
     b.dump();
     assert_eq!(b.interval_count(), 4);
 }
+
+#[test]
+fn used_ranges_and_use_range() {
+    common::init_tracing();
+    let mut a = ValueAllocator::new(1u16, u16::MAX);
+    assert!(a.used_ranges().is_empty());
+
+    assert!(a.use_range(3, 5));
+    assert!(a.use_value(9));
+    assert!(a.use_range(u16::MAX - 1, u16::MAX));
+    assert_eq!(
+        a.used_ranges(),
+        vec![(3, 5), (9, 9), (u16::MAX - 1, u16::MAX)]
+    );
+
+    // Overlapping, reversed or out of range ranges are rejected
+    assert!(!a.use_range(5, 7));
+    assert!(!a.use_range(7, 6));
+    assert!(!a.use_range(0, 1));
+    assert_eq!(
+        a.used_ranges(),
+        vec![(3, 5), (9, 9), (u16::MAX - 1, u16::MAX)]
+    );
+
+    assert!(a.use_range(1, 2));
+    assert_eq!(a.used_ranges()[..2], [(1, 5), (9, 9)]);
+    assert!(a.use_range(6, 8));
+    // Adjacent used values are reported as a single range
+    assert_eq!(a.used_ranges(), vec![(1, 9), (u16::MAX - 1, u16::MAX)]);
+}
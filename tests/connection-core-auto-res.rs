@@ -526,3 +526,125 @@ fn auto_ping_response_server_v5_0() {
     }
     assert!(pingresp_found, "PINGRESP should be found in events");
 }
+
+#[test]
+fn auto_pub_response_active_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert!(!con.auto_pub_response_active());
+
+    // Enabled but not connected
+    con.set_auto_pub_response(true);
+    assert!(!con.auto_pub_response_active());
+
+    // Enabled and connected
+    common::v5_0_client_establish_connection(&mut con);
+    assert!(con.auto_pub_response_active());
+
+    // Disabled and connected
+    con.set_auto_pub_response(false);
+    assert!(!con.auto_pub_response_active());
+
+    // Enabled and disconnected again
+    con.set_auto_pub_response(true);
+    let _events = con.notify_closed();
+    assert!(!con.auto_pub_response_active());
+}
+
+#[test]
+fn auto_ping_response_active_v3_1_1() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    assert!(!con.auto_ping_response_active());
+
+    // Enabled but not connected
+    con.set_auto_ping_response(true);
+    assert!(!con.auto_ping_response_active());
+
+    // Enabled and connected
+    common::v3_1_1_server_establish_connection(&mut con, true, false);
+    assert!(con.auto_ping_response_active());
+
+    // Disabled and connected
+    con.set_auto_ping_response(false);
+    assert!(!con.auto_ping_response_active());
+}
+
+#[test]
+fn auto_ping_response_active_client() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_auto_ping_response(true);
+    common::v5_0_client_establish_connection(&mut con);
+
+    // Clients never answer PINGREQ
+    assert!(!con.auto_ping_response_active());
+}
+
+#[test]
+fn notify_auto_responded_publish_qos1_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_auto_pub_response(true);
+    con.set_notify_auto_responded(true);
+    common::v5_0_client_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Puback(_),
+            ..
+        }
+    )));
+    match events.last().unwrap() {
+        mqtt::connection::Event::NotifyPacketReceivedEx {
+            packet: mqtt::packet::Packet::V5_0Publish(p),
+            auto_responded,
+        } => {
+            assert_eq!(p.payload().as_slice(), b"payload");
+            assert!(*auto_responded);
+        }
+        e => panic!("Expected NotifyPacketReceivedEx event, got {e:?}"),
+    }
+}
+
+#[test]
+fn notify_auto_responded_not_responded_v3_1_1() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    con.set_notify_auto_responded(true);
+    common::v3_1_1_client_establish_connection(&mut con, true, false);
+
+    // auto_pub_response is disabled, the application must respond
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    match events.last().unwrap() {
+        mqtt::connection::Event::NotifyPacketReceivedEx {
+            packet: mqtt::packet::Packet::V3_1_1Publish(_),
+            auto_responded,
+        } => {
+            assert!(!*auto_responded);
+        }
+        e => panic!("Expected NotifyPacketReceivedEx event, got {e:?}"),
+    }
+}
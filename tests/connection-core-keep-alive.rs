@@ -281,3 +281,194 @@ fn client_receive_connack_server_keep_alive_prop_0to1() {
         panic!("Expected NotifyPacketReceived event, got: {:?}", events[1]);
     }
 }
+
+#[test]
+fn client_receive_connack_server_keep_alive_prop_60to30() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .keep_alive(60u16)
+        .build()
+        .unwrap();
+    let events = connection.checked_send(connect);
+    // PingreqSend timer is armed with the CONNECT keep alive
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::PingreqSend,
+            duration_ms: 60000
+        }
+    )));
+
+    // Override by server keep alive
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::ServerKeepAlive::new(30).unwrap().into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 2);
+
+    // The armed PingreqSend timer is re-armed with the new interval
+    if let mqtt::connection::Event::RequestTimerReset { kind, duration_ms } = &events[0] {
+        assert_eq!(*kind, mqtt::connection::TimerKind::PingreqSend);
+        assert_eq!(*duration_ms, 30000);
+    } else {
+        panic!("Expected RequestTimerReset event, got: {:?}", events[0]);
+    }
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyPacketReceived(_)
+    ));
+
+    // Subsequent sends keep using the new interval
+    let pingreq = mqtt::packet::v5_0::Pingreq::builder().build().unwrap();
+    let events = connection.checked_send(pingreq);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::PingreqSend,
+            duration_ms: 30000
+        }
+    )));
+}
+
+#[test]
+fn server_min_keep_alive_raise_5to30_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    connection.set_min_keep_alive(Some(30));
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .keep_alive(5u16)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+    assert_eq!(connection.server_keep_alive(), Some(30));
+
+    let connack = connection
+        .build_v5_0_connack(
+            false,
+            mqtt::result_code::ConnectReasonCode::Success,
+            Vec::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        connack.props(),
+        &vec![mqtt::packet::ServerKeepAlive::new(30).unwrap().into()]
+    );
+
+    // Sending the CONNACK re-arms the PINGREQ receive timer with the raised keep alive
+    let events = connection.checked_send(connack);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::PingreqRecv,
+            duration_ms: 45000
+        }
+    )));
+}
+
+#[test]
+fn server_min_keep_alive_not_below_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    connection.set_min_keep_alive(Some(30));
+    connection.set_reject_below_min_keep_alive(true);
+
+    // Zero disables keep alive and is not subject to the minimum
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .keep_alive(0u16)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+    assert_eq!(connection.server_keep_alive(), None);
+
+    let connack = connection
+        .build_v5_0_connack(
+            false,
+            mqtt::result_code::ConnectReasonCode::Success,
+            Vec::new(),
+        )
+        .unwrap();
+    assert!(connack.props().is_empty());
+}
+
+#[test]
+fn server_min_keep_alive_reject_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    connection.set_min_keep_alive(Some(30));
+    connection.set_reject_below_min_keep_alive(true);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .keep_alive(5u16)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(|e| match e {
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Connack(connack),
+            ..
+        } => {
+            connack.reason_code()
+                == mqtt::result_code::ConnectReasonCode::ImplementationSpecificError
+        }
+        _ => false,
+    }));
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::KeepAliveBelowMinimum
+        ))
+    ));
+}
+
+#[test]
+fn server_min_keep_alive_reject_v3_1_1() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    connection.set_min_keep_alive(Some(30));
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .keep_alive(5u16)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(|e| match e {
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V3_1_1Connack(connack),
+            ..
+        } => connack.return_code() == mqtt::result_code::ConnectReturnCode::ServerUnavailable,
+        _ => false,
+    }));
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::KeepAliveBelowMinimum
+        ))
+    ));
+}
@@ -377,6 +377,217 @@ fn client_over_maximum_packet_size_recv() {
     }
 }
 
+#[test]
+fn client_over_maximum_packet_size_recv_disconnect_reason_string() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect_packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test-client")
+        .unwrap()
+        .clean_start(true)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(30)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _events = con.checked_send(connect_packet);
+
+    let connack_packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .expect("Failed to build Connack packet");
+    let bytes = connack_packet.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    con.set_error_context(true);
+
+    // DISCONNECT that only exceeds the limit because of its ReasonString
+    let disconnect = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .props(vec![mqtt::packet::ReasonString::new(
+            "the server is shutting down for scheduled maintenance",
+        )
+        .unwrap()
+        .into()])
+        .build()
+        .unwrap();
+    assert!(disconnect.size() > 30);
+    let bytes = disconnect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 4);
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        let expected_disconnect: mqtt::packet::Packet = mqtt::packet::v5_0::Disconnect::builder()
+            .reason_code(mqtt::result_code::DisconnectReasonCode::PacketTooLarge)
+            .build()
+            .unwrap()
+            .into();
+        assert_eq!(*packet, expected_disconnect);
+    } else {
+        panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    // The cause is reported distinctly from a packet that is too large by itself
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::OptionalPropertiesTooLarge
+        )
+    ));
+    // The oversized packet is identified as the received DISCONNECT
+    if let mqtt::connection::Event::NotifyErrorContext(context) = &events[3] {
+        assert_eq!(
+            *context,
+            mqtt::connection::MqttErrorContext {
+                error: mqtt::result_code::MqttError::OptionalPropertiesTooLarge,
+                packet_type: Some(mqtt::packet::PacketType::Disconnect),
+                packet_id: None,
+            }
+        );
+    } else {
+        panic!(
+            "Expected NotifyErrorContext event, but got: {:?}",
+            events[3]
+        );
+    }
+}
+
+#[test]
+fn client_over_maximum_packet_size_recv_puback_user_property() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect_packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test-client")
+        .unwrap()
+        .clean_start(true)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(30)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _events = con.checked_send(connect_packet);
+
+    let connack_packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .expect("Failed to build Connack packet");
+    let bytes = connack_packet.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // PUBACK that only exceeds the limit because of its UserProperty
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(1)
+        .reason_code(mqtt::result_code::PubackReasonCode::Success)
+        .props(vec![mqtt::packet::UserProperty::new(
+            "diagnostic",
+            "the message was delivered to all subscribers",
+        )
+        .unwrap()
+        .into()])
+        .build()
+        .unwrap();
+    assert!(puback.size() > 30);
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::OptionalPropertiesTooLarge
+        )
+    ));
+}
+
+#[test]
+fn client_over_maximum_packet_size_recv_connack_reason_string() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect_packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test-client")
+        .unwrap()
+        .clean_start(true)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(30)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _events = con.checked_send(connect_packet);
+
+    // CONNACK that only exceeds the limit because of its ReasonString
+    let connack_packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::ReasonString::new(
+            "the connection is accepted by the server",
+        )
+        .unwrap()
+        .into()])
+        .build()
+        .expect("Failed to build Connack packet");
+    assert!(connack_packet.size() > 30);
+    let bytes = connack_packet.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::OptionalPropertiesTooLarge
+        )
+    )));
+}
+
+#[test]
+fn client_over_maximum_packet_size_recv_suback_reason_string() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect_packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test-client")
+        .unwrap()
+        .clean_start(true)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(30)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _events = con.checked_send(connect_packet);
+
+    let connack_packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .expect("Failed to build Connack packet");
+    let bytes = connack_packet.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // SUBACK that only exceeds the limit because of its ReasonString
+    let suback_packet = mqtt::packet::v5_0::Suback::builder()
+        .packet_id(1u16)
+        .reason_codes(vec![mqtt::result_code::SubackReasonCode::GrantedQos0])
+        .props(vec![mqtt::packet::ReasonString::new(
+            "the subscription is granted by the server",
+        )
+        .unwrap()
+        .into()])
+        .build()
+        .expect("Failed to build Suback packet");
+    assert!(suback_packet.size() > 30);
+    let bytes = suback_packet.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::OptionalPropertiesTooLarge
+        )
+    )));
+}
+
 #[test]
 fn server_over_maximum_packet_size_recv() {
     common::init_tracing();
@@ -503,3 +714,141 @@ fn server_over_maximum_packet_size_send() {
         "Expected PacketTooLarge error for PUBLISH packet"
     );
 }
+
+#[test]
+fn max_total_packet_size_after_connack_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(con.max_remaining_length(), 268_435_455);
+    assert_eq!(con.max_total_packet_size(), 268_435_460);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(1000)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(con.max_remaining_length(), 268_435_455);
+    assert_eq!(con.max_total_packet_size(), 1000);
+
+    let _ = con.notify_closed();
+    assert_eq!(con.max_total_packet_size(), 268_435_460);
+}
+
+#[test]
+fn max_packet_size_constants() {
+    common::init_tracing();
+    // Maximum Variable Byte Integer value is 128^4 - 1
+    assert_eq!(
+        mqtt::connection::MQTT_MAX_REMAINING_LENGTH,
+        128 * 128 * 128 * 128 - 1
+    );
+    // 1 (fixed header) + 4 (remaining length) + maximum remaining length
+    assert_eq!(
+        mqtt::connection::MQTT_MAX_PACKET_SIZE,
+        1 + 4 + (128 * 128 * 128 * 128 - 1)
+    );
+    assert_eq!(mqtt::connection::MQTT_MAX_PACKET_SIZE, 268_435_460);
+
+    let con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(
+        con.max_remaining_length(),
+        mqtt::connection::MQTT_MAX_REMAINING_LENGTH
+    );
+    assert_eq!(
+        con.max_total_packet_size(),
+        mqtt::connection::MQTT_MAX_PACKET_SIZE
+    );
+}
+
+fn v5_0_client_auto_disconnect(
+    maximum_packet_size: u32,
+    reason_string: &str,
+) -> Vec<mqtt::connection::Event> {
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_auto_disconnect_reason_string(Some(reason_string.to_string()));
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(
+            maximum_packet_size,
+        )
+        .unwrap()
+        .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // PUBACK for an unknown packet id causes an automatic DISCONNECT
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(1u16)
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    con.recv(&mut mqtt::common::Cursor::new(&bytes))
+}
+
+#[test]
+fn auto_disconnect_reason_string_fits() {
+    common::init_tracing();
+    let events = v5_0_client_auto_disconnect(100, "unknown packet id");
+
+    assert_eq!(events.len(), 3);
+    let expected: mqtt::packet::Packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+        .props(vec![mqtt::packet::ReasonString::new("unknown packet id")
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap()
+        .into();
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        assert_eq!(*packet, expected);
+    } else {
+        panic!("Expected RequestSendPacket event, got: {:?}", events[0]);
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+}
+
+#[test]
+fn auto_disconnect_reason_string_trimmed_to_fit() {
+    common::init_tracing();
+    let long_reason = "x".repeat(100);
+    let events = v5_0_client_auto_disconnect(20, &long_reason);
+
+    assert_eq!(events.len(), 3);
+    let expected: mqtt::packet::Packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+        .build()
+        .unwrap()
+        .into();
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        assert_eq!(*packet, expected);
+    } else {
+        panic!("Expected RequestSendPacket event, got: {:?}", events[0]);
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
@@ -457,6 +457,164 @@ fn suback_no_match_v3_1_1() {
     }
 }
 
+#[test]
+fn suback_no_match_ignored_v3_1_1() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    connection.set_ignore_unknown_suback(true);
+    v3_1_1_client_establish_connection(&mut connection, true, false);
+
+    let suback = mqtt::packet::v3_1_1::Suback::builder()
+        .packet_id(1u16)
+        .return_codes(vec![
+            mqtt::result_code::SubackReturnCode::SuccessMaximumQos0,
+        ])
+        .build()
+        .unwrap();
+    let bytes = suback.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::NotifyPacketReceived(packet) => {
+            assert_eq!(*packet, suback.into());
+        }
+        _ => panic!("Expected NotifyPacketReceived event, got {:?}", events[0]),
+    }
+}
+
+#[test]
+fn unsuback_no_match_ignored_v5_0() {
+    common::init_tracing();
+    let unsuback = mqtt::packet::v5_0::Unsuback::builder()
+        .packet_id(1u16)
+        .reason_codes(vec![mqtt::result_code::UnsubackReasonCode::Success])
+        .build()
+        .unwrap();
+    let bytes = unsuback.to_continuous_buffer();
+
+    // Strict (default): the connection is closed
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut connection);
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestClose)));
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::ProtocolError
+        ))
+    ));
+
+    // Tolerant: the packet is notified and the connection stays open
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_ignore_unknown_suback(true);
+    v5_0_client_establish_connection(&mut connection);
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::NotifyPacketReceived(packet) => {
+            assert_eq!(*packet, unsuback.into());
+        }
+        _ => panic!("Expected NotifyPacketReceived event, got {:?}", events[0]),
+    }
+    let events = connection.send(mqtt::packet::v5_0::Pingreq::new().into());
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+}
+
+#[test]
+fn suback_code_count_mismatch_v3_1_1() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut connection, true, false);
+
+    let packet_id = connection.acquire_packet_id().unwrap();
+    let subscribe = mqtt::packet::v3_1_1::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(vec![
+            mqtt::packet::SubEntry::new("topic/a", mqtt::packet::SubOpts::default()).unwrap(),
+            mqtt::packet::SubEntry::new("topic/b", mqtt::packet::SubOpts::default()).unwrap(),
+            mqtt::packet::SubEntry::new("topic/c", mqtt::packet::SubOpts::default()).unwrap(),
+        ])
+        .build()
+        .unwrap();
+    let _events = connection.checked_send(subscribe);
+
+    let suback = mqtt::packet::v3_1_1::Suback::builder()
+        .packet_id(packet_id)
+        .return_codes(vec![
+            mqtt::result_code::SubackReturnCode::SuccessMaximumQos0,
+            mqtt::result_code::SubackReturnCode::SuccessMaximumQos0,
+        ])
+        .build()
+        .unwrap();
+    let bytes = suback.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+
+    // The packet ID is still held and released on close
+    let events = connection.notify_closed();
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::NotifyPacketIdReleased(id) if *id == packet_id
+    )));
+}
+
+#[test]
+fn suback_code_count_mismatch_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut connection);
+
+    let packet_id = connection.acquire_packet_id().unwrap();
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(vec![
+            mqtt::packet::SubEntry::new("topic/a", mqtt::packet::SubOpts::default()).unwrap(),
+            mqtt::packet::SubEntry::new("topic/b", mqtt::packet::SubOpts::default()).unwrap(),
+        ])
+        .build()
+        .unwrap();
+    let _events = connection.checked_send(subscribe);
+
+    let suback = mqtt::packet::v5_0::Suback::builder()
+        .packet_id(packet_id)
+        .reason_codes(vec![
+            mqtt::result_code::SubackReasonCode::GrantedQos0,
+            mqtt::result_code::SubackReasonCode::GrantedQos0,
+            mqtt::result_code::SubackReasonCode::GrantedQos0,
+        ])
+        .build()
+        .unwrap();
+    let bytes = suback.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 3);
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+            assert_eq!(
+                disconnect.reason_code(),
+                Some(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+            );
+        } else {
+            panic!("Expected V5_0Disconnect packet, got {:?}", packet);
+        }
+    } else {
+        panic!("Expected RequestSendPacket event, got {:?}", events[0]);
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
 #[test]
 fn unsuback_match_v3_1_1() {
     common::init_tracing();
@@ -893,6 +1051,47 @@ fn packet_id_management_v3_1_1() {
     assert_eq!(new_acquired_id, 1);
 }
 
+#[test]
+fn packet_id_acquire_across_u16_boundary() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+
+    // Acquire every id; ids are handed out lowest first and never 0
+    for expected in 1..=u16::MAX {
+        assert_eq!(connection.acquire_packet_id().unwrap(), expected);
+    }
+    assert_eq!(
+        connection.acquire_packet_id(),
+        Err(mqtt::result_code::MqttError::PacketIdentifierFullyUsed)
+    );
+    assert_eq!(connection.peek_next_packet_id(), None);
+
+    // The highest id is reused after release
+    let events = connection.release_packet_id(u16::MAX);
+    assert_eq!(events.len(), 1);
+    assert_eq!(connection.acquire_packet_id().unwrap(), u16::MAX);
+
+    // The lowest free id is reused first
+    let _ = connection.release_packet_id(u16::MAX);
+    let _ = connection.release_packet_id(1);
+    assert_eq!(connection.acquire_packet_id().unwrap(), 1);
+    assert_eq!(connection.acquire_packet_id().unwrap(), u16::MAX);
+    assert_eq!(
+        connection.acquire_packet_id(),
+        Err(mqtt::result_code::MqttError::PacketIdentifierFullyUsed)
+    );
+
+    // Packet id 0 is never in use and can be neither registered nor released
+    assert!(connection.register_packet_id(0).is_err());
+    assert!(connection.release_packet_id(0).is_empty());
+
+    // Release everything and wrap around to the lowest id again
+    for id in 1..=u16::MAX {
+        let _ = connection.release_packet_id(id);
+    }
+    assert_eq!(connection.acquire_packet_id().unwrap(), 1);
+}
+
 #[test]
 fn qos2_publish_handled_v3_1_1() {
     common::init_tracing();
@@ -1467,3 +1666,1185 @@ fn connack_session_expiry_interval_absent_v5_0() {
     let stored = con.get_stored_packets();
     assert_eq!(stored.len(), 1);
 }
+
+#[test]
+fn capture_last_sent_connect_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert!(con.last_sent_bytes().is_none());
+    con.set_capture_last_sent(true);
+
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let expected = packet.to_continuous_buffer();
+    let _events = con.checked_send(packet);
+    assert_eq!(con.last_sent_bytes(), Some(expected.as_slice()));
+
+    // Disabling the capture drops the kept bytes
+    con.set_capture_last_sent(false);
+    assert!(con.last_sent_bytes().is_none());
+}
+
+fn prefix_publish_topic(packet: mqtt::packet::Packet) -> mqtt::packet::Packet {
+    match packet {
+        mqtt::packet::Packet::V5_0Publish(publish) => {
+            let topic_name = format!("gw/{}", publish.topic_name());
+            mqtt::packet::v5_0::Publish::builder()
+                .topic_name(topic_name)
+                .unwrap()
+                .qos(publish.qos())
+                .packet_id(publish.packet_id())
+                .props(publish.props.clone())
+                .payload(publish.payload().as_slice().to_vec())
+                .build()
+                .unwrap()
+                .into()
+        }
+        packet => packet,
+    }
+}
+
+#[test]
+fn outgoing_transform_rewrites_publish_topic_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut con);
+    con.set_outgoing_transform(Some(prefix_publish_topic));
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let events = con.checked_send(publish);
+
+    let sent = events.iter().find_map(|e| match e {
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Publish(publish),
+            ..
+        } => Some(publish),
+        _ => None,
+    });
+    let sent = sent.expect("Expected RequestSendPacket with PUBLISH");
+    assert_eq!(sent.topic_name(), "gw/topic/a");
+    assert_eq!(sent.packet_id(), Some(packet_id));
+}
+
+#[test]
+fn outgoing_transform_skips_stored_packets_resent_on_connack_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    con.restore_packets(vec![mqtt::packet::GenericStorePacket::V5_0Publish(publish)]);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .clean_start(false)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    con.set_outgoing_transform(Some(prefix_publish_topic));
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(true)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let events = con.checked_send(connack);
+
+    let resent = events.iter().find_map(|e| match e {
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Publish(publish),
+            ..
+        } => Some(publish),
+        _ => None,
+    });
+    let resent = resent.expect("Expected RequestSendPacket with stored PUBLISH");
+    assert_eq!(resent.topic_name(), "topic/a");
+}
+
+#[test]
+fn outgoing_transform_stores_transformed_packet_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    // Setup connection - need SessionExpiryInterval to enable store
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(3600)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    con.set_outgoing_transform(Some(prefix_publish_topic));
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let _events = con.checked_send(publish);
+
+    let stored = con.get_stored_packets();
+    assert_eq!(stored.len(), 1);
+    match &stored[0] {
+        mqtt::packet::GenericStorePacket::V5_0Publish(publish) => {
+            assert_eq!(publish.topic_name(), "gw/topic/a");
+        }
+        _ => panic!("Expected stored PUBLISH"),
+    }
+}
+
+#[test]
+fn outgoing_transform_result_checked_against_maximum_packet_size_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+
+    // The original PUBLISH is 15 bytes, the transformed one is 18 bytes
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::MaximumPacketSize::new(16)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connack);
+
+    con.set_outgoing_transform(Some(prefix_publish_topic));
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test/topic")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtMostOnce)
+        .build()
+        .unwrap();
+    assert_eq!(publish.size(), 15);
+    let events = con.checked_send(publish);
+
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketTooLarge)
+    ));
+}
+
+std::thread_local! {
+    static OBSERVED: std::cell::RefCell<Vec<(mqtt::connection::Direction, mqtt::packet::PacketType)>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn record_packet(direction: mqtt::connection::Direction, packet: &mqtt::packet::Packet) {
+    OBSERVED.with(|observed| {
+        observed
+            .borrow_mut()
+            .push((direction, packet.packet_type()))
+    });
+}
+
+#[test]
+fn packet_observer_send_and_recv_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_packet_observer(Some(record_packet));
+
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _events = con.checked_send(packet);
+
+    let packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // Removing the observer stops notifications
+    con.set_packet_observer(None);
+    let _events = con.send(mqtt::packet::v5_0::Pingreq::new().into());
+
+    let observed = OBSERVED.with(|observed| observed.borrow().clone());
+    assert_eq!(
+        observed,
+        vec![
+            (
+                mqtt::connection::Direction::Send,
+                mqtt::packet::PacketType::Connect
+            ),
+            (
+                mqtt::connection::Direction::Recv,
+                mqtt::packet::PacketType::Connack
+            ),
+        ]
+    );
+}
+
+#[test]
+fn peek_next_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    // Peek is idempotent
+    assert_eq!(con.peek_next_packet_id(), Some(1));
+    assert_eq!(con.peek_next_packet_id(), Some(1));
+
+    // Peek returns the same id that a subsequent acquire returns
+    let peeked = con.peek_next_packet_id();
+    assert_eq!(con.acquire_packet_id().ok(), peeked);
+    assert_eq!(con.peek_next_packet_id(), Some(2));
+
+    // Registered ids are skipped, released ids become next again
+    con.register_packet_id(2).unwrap();
+    assert_eq!(con.peek_next_packet_id(), Some(3));
+    let _events = con.release_packet_id(1);
+    assert_eq!(con.peek_next_packet_id(), Some(1));
+}
+
+#[test]
+fn peek_next_packet_id_fully_used() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    for _ in 0..u16::MAX {
+        con.acquire_packet_id().unwrap();
+    }
+    assert_eq!(con.peek_next_packet_id(), None);
+    assert_eq!(
+        con.acquire_packet_id(),
+        Err(mqtt::result_code::MqttError::PacketIdentifierFullyUsed)
+    );
+}
+
+#[test]
+fn inferred_role_any_client() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V5_0);
+    assert_eq!(con.inferred_role(), None);
+
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _events = con.send(packet.into());
+    assert_eq!(
+        con.inferred_role(),
+        Some(mqtt::role::ConnectionRole::Client)
+    );
+}
+
+#[test]
+fn inferred_role_any_server() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V3_1_1);
+    assert_eq!(con.inferred_role(), None);
+
+    let packet = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(
+        con.inferred_role(),
+        Some(mqtt::role::ConnectionRole::Server)
+    );
+}
+
+#[test]
+fn inferred_role_concrete() {
+    common::init_tracing();
+    let client = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(
+        client.inferred_role(),
+        Some(mqtt::role::ConnectionRole::Client)
+    );
+    let server = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    assert_eq!(
+        server.inferred_role(),
+        Some(mqtt::role::ConnectionRole::Server)
+    );
+}
+
+#[test]
+fn abort_inbound_qos2_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(con.get_qos2_publish_handled().contains(&1u16));
+    con.set_capture_last_sent(true);
+
+    let events = con.abort_inbound_qos2(1u16);
+    assert_eq!(events.len(), 1);
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        if let mqtt::packet::Packet::V5_0Pubrec(pubrec) = packet {
+            assert_eq!(pubrec.packet_id(), 1u16);
+            assert_eq!(
+                pubrec.reason_code(),
+                Some(mqtt::result_code::PubrecReasonCode::UnspecifiedError)
+            );
+            // The sent PUBREC is observed like any other sent packet
+            assert_eq!(
+                con.last_sent_bytes(),
+                Some(pubrec.to_continuous_buffer().as_slice())
+            );
+        } else {
+            panic!("Expected V5_0Pubrec packet, got {packet:?}");
+        }
+    } else {
+        panic!("Expected RequestSendPacket event, got {:?}", events[0]);
+    }
+    assert!(con.get_qos2_publish_handled().is_empty());
+
+    // The same packet id is treated as a new flow afterwards
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+
+    // Aborting an unknown packet id does nothing
+    let events = con.abort_inbound_qos2(2u16);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn abort_inbound_qos2_after_auto_pubrec_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_auto_pub_response(true);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Pubrec(_),
+            ..
+        }
+    )));
+
+    // PUBREC(Success) has already been sent, so no second PUBREC is sent
+    let events = con.abort_inbound_qos2(1u16);
+    assert!(events.is_empty());
+    assert!(con.get_qos2_publish_handled().is_empty());
+
+    // The peer's PUBREL is still answered with PUBCOMP
+    let pubrel = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(1u16)
+        .build()
+        .unwrap();
+    let bytes = pubrel.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestSendPacket {
+            packet: mqtt::packet::Packet::V5_0Pubcomp(_),
+            ..
+        }
+    )));
+}
+
+#[test]
+fn abort_inbound_qos2_v3_1_1_not_connected() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, false, false);
+
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    let _events = con.notify_closed();
+
+    let mut handled = mqtt::common::HashSet::default();
+    handled.insert(1u16);
+    con.restore_qos2_publish_handled(handled);
+
+    let events = con.abort_inbound_qos2(1u16);
+    assert!(events.is_empty());
+    assert!(con.get_qos2_publish_handled().is_empty());
+}
+
+#[test]
+fn clear_qos2_publish_handled_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_auto_pub_response(true);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+    assert!(con.get_qos2_publish_handled().contains(&1));
+
+    con.clear_qos2_publish_handled();
+    assert!(con.get_qos2_publish_handled().is_empty());
+
+    // The previously handled packet ID is treated as a new message
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+}
+
+#[test]
+fn acknowledge_many_qos1_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_auto_pub_response(false);
+    v5_0_server_establish_connection(&mut con);
+
+    for packet_id in 1..=3u16 {
+        let publish = mqtt::packet::v5_0::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .qos(mqtt::packet::Qos::AtLeastOnce)
+            .packet_id(packet_id)
+            .payload(b"payload".to_vec())
+            .build()
+            .unwrap();
+        let bytes = publish.to_continuous_buffer();
+        let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+    }
+
+    let events = con.acknowledge_many(&[1, 2, 3]);
+    let acked: Vec<u16> = events
+        .iter()
+        .filter_map(|e| match e {
+            mqtt::connection::Event::RequestSendPacket {
+                packet: mqtt::packet::Packet::V5_0Puback(puback),
+                ..
+            } => Some(puback.packet_id()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(acked, vec![1, 2, 3]);
+
+    // Already acknowledged packet IDs are rejected
+    let events = con.acknowledge_many(&[1]);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketIdentifierInvalid)
+    ));
+}
+
+fn v5_0_client_connect_with_connack_props(
+    con: &mut mqtt::Connection<mqtt::role::Client>,
+    props: Vec<mqtt::packet::Property>,
+) {
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(props)
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+}
+
+#[test]
+fn session_expiry_interval_overridden_by_connack_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(con.session_expiry_interval(), None);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(300)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    assert_eq!(con.session_expiry_interval(), Some(300));
+
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(60)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(con.session_expiry_interval(), Some(60));
+}
+
+#[test]
+fn supports_retain_not_available_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert!(con.supports_retain());
+
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![mqtt::packet::RetainAvailable::new(0).unwrap().into()],
+    );
+
+    assert!(!con.supports_retain());
+    assert!(con.supports_wildcard_subscriptions());
+    assert!(con.supports_shared_subscriptions());
+    assert!(con.supports_subscription_identifiers());
+}
+
+#[test]
+fn validate_subscribe_wildcard_not_available_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![mqtt::packet::WildcardSubscriptionAvailable::new(0)
+            .unwrap()
+            .into()],
+    );
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(vec![
+            mqtt::packet::SubEntry::new("a/b", mqtt::packet::SubOpts::default()).unwrap(),
+            mqtt::packet::SubEntry::new("a/+", mqtt::packet::SubOpts::default()).unwrap(),
+        ])
+        .build()
+        .unwrap();
+    assert_eq!(
+        con.validate_subscribe(&subscribe),
+        Err(mqtt::result_code::MqttError::WildcardSubscriptionsNotSupported)
+    );
+
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(vec![mqtt::packet::SubEntry::new(
+            "a/b",
+            mqtt::packet::SubOpts::default(),
+        )
+        .unwrap()])
+        .build()
+        .unwrap();
+    assert_eq!(con.validate_subscribe(&subscribe), Ok(()));
+}
+
+#[test]
+fn validate_subscribe_first_violation_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![
+            mqtt::packet::WildcardSubscriptionAvailable::new(0)
+                .unwrap()
+                .into(),
+            mqtt::packet::SharedSubscriptionAvailable::new(0)
+                .unwrap()
+                .into(),
+            mqtt::packet::SubscriptionIdentifierAvailable::new(0)
+                .unwrap()
+                .into(),
+        ],
+    );
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let entries = vec![
+        mqtt::packet::SubEntry::new("$share/g/a/b", mqtt::packet::SubOpts::default()).unwrap(),
+        mqtt::packet::SubEntry::new("a/#", mqtt::packet::SubOpts::default()).unwrap(),
+    ];
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(entries.clone())
+        .props(vec![mqtt::packet::SubscriptionIdentifier::new(1)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    assert_eq!(
+        con.validate_subscribe(&subscribe),
+        Err(mqtt::result_code::MqttError::SubscriptionIdentifiersNotSupported)
+    );
+
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(entries)
+        .build()
+        .unwrap();
+    assert_eq!(
+        con.validate_subscribe(&subscribe),
+        Err(mqtt::result_code::MqttError::SharedSubscriptionsNotSupported)
+    );
+}
+
+#[test]
+fn validate_subscribe_packet_too_large_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![mqtt::packet::MaximumPacketSize::new(10).unwrap().into()],
+    );
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let subscribe = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(packet_id)
+        .entries(vec![mqtt::packet::SubEntry::new(
+            "a/long/topic/filter",
+            mqtt::packet::SubOpts::default(),
+        )
+        .unwrap()])
+        .build()
+        .unwrap();
+    assert_eq!(
+        con.validate_subscribe(&subscribe),
+        Err(mqtt::result_code::MqttError::PacketTooLarge)
+    );
+}
+
+std::thread_local! {
+    static DISCONNECT_CAUSES: std::cell::RefCell<Vec<mqtt::connection::DisconnectCause>> =
+        const { std::cell::RefCell::new(Vec::new()) };
+}
+
+fn record_disconnect_cause(cause: mqtt::connection::DisconnectCause) {
+    DISCONNECT_CAUSES.with(|causes| causes.borrow_mut().push(cause));
+}
+
+fn take_disconnect_causes() -> Vec<mqtt::connection::DisconnectCause> {
+    DISCONNECT_CAUSES.with(|causes| causes.borrow_mut().drain(..).collect())
+}
+
+#[test]
+fn on_disconnected_sent_and_recv_disconnect_v5_0() {
+    common::init_tracing();
+    take_disconnect_causes();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+
+    // Sent DISCONNECT
+    v5_0_client_establish_connection(&mut con);
+    let disconnect = mqtt::packet::v5_0::Disconnect::builder().build().unwrap();
+    let _ = con.send(disconnect.into());
+    // Already disconnected, notify_closed() doesn't call the callback again
+    let _ = con.notify_closed();
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::SentDisconnect]
+    );
+
+    // Received DISCONNECT
+    v5_0_client_establish_connection(&mut con);
+    let disconnect = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .build()
+        .unwrap();
+    let bytes = disconnect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    // The I/O layer closes the socket, the callback has already been called
+    let _ = con.notify_closed();
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::RecvDisconnect]
+    );
+
+    // Closed by the I/O layer
+    v5_0_client_establish_connection(&mut con);
+    let _ = con.notify_closed();
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::Closed]
+    );
+}
+
+#[test]
+fn on_disconnected_recv_disconnect_then_connect_v5_0() {
+    common::init_tracing();
+    take_disconnect_causes();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+    v5_0_server_establish_connection(&mut con);
+
+    // DISCONNECT and CONNECT in the same buffer
+    let disconnect = mqtt::packet::v5_0::Disconnect::builder().build().unwrap();
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let mut bytes = disconnect.to_continuous_buffer();
+    bytes.extend(connect.to_continuous_buffer());
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // Receiving DISCONNECT doesn't start a new session, the CONNECT is a protocol error
+    assert!(!events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::NotifyPacketReceived(mqtt::packet::Packet::V5_0Connect(_))
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    )));
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::RecvDisconnect]
+    );
+}
+
+#[test]
+fn on_disconnected_connack_failure() {
+    common::init_tracing();
+    take_disconnect_causes();
+
+    // Server sends CONNACK with failure
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+    v5_0_server_connecting(&mut con);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::NotAuthorized)
+        .build()
+        .unwrap();
+    let _ = con.send(connack.into());
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::ConnackFailure]
+    );
+
+    // Client receives CONNACK with failure
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+    v3_1_1_client_connecting(&mut con, true);
+    let connack = mqtt::packet::v3_1_1::Connack::builder()
+        .session_present(false)
+        .return_code(mqtt::result_code::ConnectReturnCode::NotAuthorized)
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::ConnackFailure]
+    );
+}
+
+#[test]
+fn on_disconnected_keep_alive_timeout_and_protocol_error() {
+    common::init_tracing();
+    take_disconnect_causes();
+
+    // Keep alive timeout
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+    v5_0_server_establish_connection(&mut con);
+    let _ = con.notify_timer_fired(mqtt::connection::TimerKind::PingreqRecv);
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::KeepAliveTimeout]
+    );
+
+    // Protocol error
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    con.set_on_disconnected(Some(record_disconnect_cause));
+    v3_1_1_client_establish_connection(&mut con, true, false);
+    // PUBACK with remaining length 1 is malformed
+    let bytes = [0x40u8, 0x01, 0x00];
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    let _ = con.notify_closed();
+    assert_eq!(
+        take_disconnect_causes(),
+        vec![mqtt::connection::DisconnectCause::ProtocolError]
+    );
+
+    // Removing the callback stops notifications
+    v3_1_1_client_establish_connection(&mut con, true, false);
+    con.set_on_disconnected(None);
+    let _ = con.notify_closed();
+    assert!(take_disconnect_causes().is_empty());
+}
+
+#[test]
+fn display_summary_mid_session_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(
+        format!("{con}"),
+        "Client V5_0 Disconnected, 0 inflight, 0 stored, keep-alive 0s"
+    );
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .keep_alive(60u16)
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(3600)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let mut packet_ids = Vec::new();
+    for _ in 0..3 {
+        let packet_id = con.acquire_packet_id().unwrap();
+        packet_ids.push(packet_id);
+        let publish = mqtt::packet::v5_0::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .qos(mqtt::packet::Qos::AtLeastOnce)
+            .packet_id(packet_id)
+            .payload(b"payload".to_vec())
+            .build()
+            .unwrap();
+        let _ = con.checked_send(publish);
+    }
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(packet_ids[0])
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(
+        format!("{con}"),
+        "Client V5_0 Connected, 2 inflight, 2 stored, keep-alive 60s"
+    );
+}
+
+#[test]
+fn display_summary_server_keep_alive_v5_0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .keep_alive(60u16)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::ServerKeepAlive::new(20).unwrap().into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // The keep alive overridden by the server is displayed
+    assert_eq!(
+        format!("{con}"),
+        "Client V5_0 Connected, 0 inflight, 0 stored, keep-alive 20s"
+    );
+}
+
+#[test]
+fn display_summary_server_v3_1_1() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V3_1_1);
+    assert_eq!(
+        format!("{con}"),
+        "Any V3_1_1 Disconnected, 0 inflight, 0 stored, keep-alive 0s"
+    );
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .keep_alive(30u16)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(
+        format!("{con}"),
+        "Server V3_1_1 Connecting, 0 inflight, 0 stored, keep-alive 30s"
+    );
+}
+
+#[test]
+fn send_rejections_oversized_publish_while_disconnected() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![mqtt::packet::MaximumPacketSize::new(20).unwrap().into()],
+    );
+    // The client disconnects; the negotiated limits remain until the socket is closed
+    let disconnect = mqtt::packet::v5_0::Disconnect::builder().build().unwrap();
+    let _ = con.send(disconnect.into());
+
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .payload("0123456789abcdefghij")
+        .build()
+        .unwrap()
+        .into();
+    let reasons = con.send_rejections(&publish);
+    assert_eq!(
+        reasons,
+        vec![
+            mqtt::result_code::MqttError::PacketTooLarge,
+            mqtt::result_code::MqttError::PacketNotAllowedToSend,
+        ]
+    );
+
+    // send() reports only the first failure
+    let events = con.send(publish);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketTooLarge)
+    ));
+}
+
+#[test]
+fn send_rejections_packet_id_and_topic_alias() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_connack_props(
+        &mut con,
+        vec![mqtt::packet::TopicAliasMaximum::new(2).unwrap().into()],
+    );
+
+    // Packet identifier that has not been acquired
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .build()
+        .unwrap()
+        .into();
+    assert_eq!(
+        con.send_rejections(&publish),
+        vec![mqtt::result_code::MqttError::PacketIdentifierInvalid]
+    );
+    let events = con.send(publish);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketIdentifierInvalid)
+    ));
+
+    let subscribe: mqtt::packet::Packet = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(2u16)
+        .entries(vec![mqtt::packet::SubEntry::new(
+            "topic/a",
+            mqtt::packet::SubOpts::default(),
+        )
+        .unwrap()])
+        .build()
+        .unwrap()
+        .into();
+    assert_eq!(
+        con.send_rejections(&subscribe),
+        vec![mqtt::result_code::MqttError::PacketIdentifierInvalid]
+    );
+
+    // TopicAlias that is not registered, and out of range
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("")
+        .unwrap()
+        .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+        .build()
+        .unwrap()
+        .into();
+    assert_eq!(
+        con.send_rejections(&publish),
+        vec![mqtt::result_code::MqttError::PacketNotAllowedToSend]
+    );
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .props(vec![mqtt::packet::TopicAlias::new(3).unwrap().into()])
+        .build()
+        .unwrap()
+        .into();
+    assert_eq!(
+        con.send_rejections(&publish),
+        vec![mqtt::result_code::MqttError::PacketNotAllowedToSend]
+    );
+    let events = con.send(publish);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketNotAllowedToSend)
+    ));
+
+    // Registered TopicAlias can be used without the topic name
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+        .build()
+        .unwrap()
+        .into();
+    assert!(con.send_rejections(&publish).is_empty());
+    let _ = con.send(publish);
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("")
+        .unwrap()
+        .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+        .build()
+        .unwrap()
+        .into();
+    assert!(con.send_rejections(&publish).is_empty());
+}
+
+#[test]
+fn send_rejections_wrong_role_and_version() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connack: mqtt::packet::Packet = mqtt::packet::v3_1_1::Connack::builder()
+        .session_present(false)
+        .return_code(mqtt::result_code::ConnectReturnCode::Accepted)
+        .build()
+        .unwrap()
+        .into();
+    let reasons = con.send_rejections(&connack);
+    // The role and the state both report PacketNotAllowedToSend, as send() does
+    assert_eq!(
+        reasons,
+        vec![
+            mqtt::result_code::MqttError::VersionMismatch,
+            mqtt::result_code::MqttError::PacketNotAllowedToSend,
+        ]
+    );
+
+    let connack: mqtt::packet::Packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap()
+        .into();
+    assert_eq!(
+        con.send_rejections(&connack),
+        vec![mqtt::result_code::MqttError::PacketNotAllowedToSend]
+    );
+    let events = con.send(connack);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketNotAllowedToSend)
+    ));
+
+    let connect: mqtt::packet::Packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap()
+        .into();
+    assert!(con.send_rejections(&connect).is_empty());
+}
+
+#[test]
+fn is_client_mode_any() {
+    common::init_tracing();
+    let mut client = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V5_0);
+    assert!(!client.is_client_mode());
+
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _events = client.send(packet.into());
+    assert!(client.is_client_mode());
+
+    let mut server = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V5_0);
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let _events = server.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(!server.is_client_mode());
+}
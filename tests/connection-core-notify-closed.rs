@@ -294,10 +294,26 @@ fn notify_closed_with_acquired_packet_ids() {
         .into();
     let _events = con.send(publish_qos2_packet);
 
-    // 5. Pubrel packet
+    // 5. Pubrel packet after PUBREC is received
     let pubrel_pid = con
         .acquire_packet_id()
         .expect("Failed to acquire packet ID for Pubrel");
+    let publish_pubrel_packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("test/qos2")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(pubrel_pid)
+        .payload(b"qos2 payload")
+        .build()
+        .expect("Failed to build Publish QoS2 packet")
+        .into();
+    let _events = con.send(publish_pubrel_packet);
+    let pubrec = mqtt::packet::v3_1_1::Pubrec::builder()
+        .packet_id(pubrel_pid)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
     let pubrel_packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Pubrel::builder()
         .packet_id(pubrel_pid)
         .build()
@@ -413,10 +429,26 @@ fn notify_closed_with_acquired_packet_ids_session_storage() {
         .into();
     let _events = con.send(publish_qos2_packet);
 
-    // 5. Pubrel packet
+    // 5. Pubrel packet after PUBREC is received
     let pubrel_pid = con
         .acquire_packet_id()
         .expect("Failed to acquire packet ID for Pubrel");
+    let publish_pubrel_packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("test/qos2")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(pubrel_pid)
+        .payload(b"qos2 payload")
+        .build()
+        .expect("Failed to build Publish QoS2 packet")
+        .into();
+    let _events = con.send(publish_pubrel_packet);
+    let pubrec = mqtt::packet::v3_1_1::Pubrec::builder()
+        .packet_id(pubrel_pid)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
     let pubrel_packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Pubrel::builder()
         .packet_id(pubrel_pid)
         .build()
@@ -528,10 +560,26 @@ fn notify_closed_v5_0_with_acquired_packet_ids() {
         .into();
     let _events = con.send(publish_qos2_packet);
 
-    // 5. Pubrel packet
+    // 5. Pubrel packet after PUBREC is received
     let pubrel_pid = con
         .acquire_packet_id()
         .expect("Failed to acquire packet ID for Pubrel");
+    let publish_pubrel_packet: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test/qos2")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(pubrel_pid)
+        .payload(b"qos2 payload")
+        .build()
+        .expect("Failed to build Publish QoS2 packet")
+        .into();
+    let _events = con.send(publish_pubrel_packet);
+    let pubrec = mqtt::packet::v5_0::Pubrec::builder()
+        .packet_id(pubrel_pid)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
     let pubrel_packet: mqtt::packet::Packet = mqtt::packet::v5_0::Pubrel::builder()
         .packet_id(pubrel_pid)
         .build()
@@ -576,3 +624,61 @@ fn notify_closed_v5_0_with_acquired_packet_ids() {
         "Expected 5 packet IDs to be released in v5.0"
     );
 }
+
+fn recv_qos2_publish(con: &mut mqtt::Connection<mqtt::role::Server>, packet_id: u16) {
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(packet_id)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+}
+
+#[test]
+fn notify_closed_keeps_qos2_handled_with_session_storage() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, false, false);
+    recv_qos2_publish(&mut con, 1);
+
+    let _ = con.notify_closed();
+    assert!(con.get_qos2_publish_handled().contains(&1));
+}
+
+#[test]
+fn notify_closed_clears_qos2_handled_without_session_storage() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+    recv_qos2_publish(&mut con, 1);
+    assert!(con.get_qos2_publish_handled().contains(&1));
+
+    let _ = con.notify_closed();
+    assert!(con.get_qos2_publish_handled().is_empty());
+}
+
+#[test]
+fn notify_closed_clear_qos2_on_close_override() {
+    common::init_tracing();
+    // Always clear even if the session is stored
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_clear_qos2_on_close(Some(true));
+    v3_1_1_server_establish_connection(&mut con, false, false);
+    recv_qos2_publish(&mut con, 1);
+
+    let _ = con.notify_closed();
+    assert!(con.get_qos2_publish_handled().is_empty());
+
+    // Always keep even if the session is not stored
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_clear_qos2_on_close(Some(false));
+    v3_1_1_server_establish_connection(&mut con, true, false);
+    recv_qos2_publish(&mut con, 1);
+
+    let _ = con.notify_closed();
+    assert!(con.get_qos2_publish_handled().contains(&1));
+}
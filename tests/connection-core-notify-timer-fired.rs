@@ -88,6 +88,49 @@ fn notify_timer_fired_pingreq_send_disconnected() {
     assert_eq!(events.len(), 0);
 }
 
+#[test]
+fn notify_timer_fired_pingreq_send_v5_0_server() {
+    common::init_tracing();
+    // Test PingreqSend timer on a server connection
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+
+    // Establish connection
+    v5_0_server_establish_connection(&mut con);
+
+    // Fire PingreqSend timer
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::PingreqSend);
+
+    // Server never sends PINGREQ
+    assert_eq!(events.len(), 0);
+}
+
+#[test]
+fn notify_timer_fired_pingreq_send_v3_1_1_any_as_server() {
+    common::init_tracing();
+    // Test PingreqSend timer on an Any connection acting as a server
+    let mut con = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V3_1_1);
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    let connack = mqtt::packet::v3_1_1::Connack::builder()
+        .session_present(false)
+        .return_code(mqtt::result_code::ConnectReturnCode::Accepted)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connack);
+
+    // Fire PingreqSend timer
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::PingreqSend);
+
+    // Server never sends PINGREQ
+    assert_eq!(events.len(), 0);
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 // Test notify_timer_fired method - PingreqRecv timer
@@ -203,3 +246,172 @@ fn notify_timer_fired_pingresp_recv_v5_0_disconnected() {
     // Should not send DISCONNECT when disconnected
     assert_eq!(events.len(), 0);
 }
+
+#[test]
+fn connect_timeout_fired_while_connecting() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_connect_timeout(Some(5000));
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let events = con.checked_send(connect);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::ConnectTimeout,
+            duration_ms: 5000
+        }
+    )));
+
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::ConnectTimeout);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+
+    // The connection is disconnected, CONNECT can be sent again
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let events = con.checked_send(connect);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+}
+
+#[test]
+fn connect_timeout_cancelled_on_connack() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_connect_timeout(Some(5000));
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::ConnectTimeout,
+            ..
+        }
+    )));
+
+    let connack = mqtt::packet::v3_1_1::Connack::builder()
+        .session_present(false)
+        .return_code(mqtt::result_code::ConnectReturnCode::Accepted)
+        .build()
+        .unwrap();
+    let events = con.checked_send(connack);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerCancel(mqtt::connection::TimerKind::ConnectTimeout)
+    )));
+
+    // A late timer has no effect once connected
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::ConnectTimeout);
+    assert!(events.is_empty());
+}
+
+#[test]
+fn connect_timeout_fires_before_keep_alive_while_connecting() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_connect_timeout(Some(1000));
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .keep_alive(60)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    // Keep-alive applies from CONNECT, the connect-phase timer is armed as well
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::PingreqRecv,
+            duration_ms: 90000
+        }
+    )));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::ConnectTimeout,
+            duration_ms: 1000
+        }
+    )));
+
+    // No CONNACK is sent, the connect-phase timer fires first
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::ConnectTimeout);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerCancel(mqtt::connection::TimerKind::PingreqRecv)
+    )));
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::RequestClose)
+    ));
+}
+
+#[test]
+fn qos2_handled_ttl_evicts_handled_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_qos2_handled_ttl(Some(100));
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let is_received = |events: &[mqtt::connection::Event]| {
+        events
+            .iter()
+            .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_)))
+    };
+
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(is_received(&events));
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::Qos2HandledExpiry,
+            duration_ms: 100
+        }
+    )));
+
+    // The first expiry only ages the ID, it is still a duplicate
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::Qos2HandledExpiry);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::Qos2HandledExpiry,
+            ..
+        }
+    )));
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(!is_received(&events));
+
+    // The second expiry evicts the ID
+    let events = con.notify_timer_fired(mqtt::connection::TimerKind::Qos2HandledExpiry);
+    assert!(events.is_empty());
+    assert!(con.get_qos2_publish_handled().is_empty());
+
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(is_received(&events));
+    assert!(con.get_qos2_publish_handled().contains(&1));
+}
@@ -514,3 +514,88 @@ fn receive_maximum_exceeded_recv_server() {
         );
     }
 }
+
+#[test]
+fn limit_status_receive_maximum_saturated() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    // Keep the session so that sent PUBLISH packets are stored
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(60)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let _events = connection.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![
+            mqtt::packet::ReceiveMaximum::new(2).unwrap().into(),
+            mqtt::packet::TopicAliasMaximum::new(4).unwrap().into(),
+        ])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let status = connection.limit_status();
+    assert_eq!(status.publish_send_inflight, 0);
+    assert_eq!(status.publish_send_max, Some(2));
+    assert_eq!(status.topic_alias_send_max, Some(4));
+    assert_eq!(status.topic_alias_recv_max, None);
+    assert!(!status.is_publish_send_saturated());
+
+    // Fill the send window
+    for topic in ["topic/a", "topic/b"] {
+        let packet_id = connection.acquire_packet_id().unwrap();
+        let publish = mqtt::packet::v5_0::Publish::builder()
+            .topic_name(topic)
+            .unwrap()
+            .qos(mqtt::packet::Qos::AtLeastOnce)
+            .packet_id(packet_id)
+            .payload(b"payload".to_vec())
+            .build()
+            .unwrap();
+        let _events = connection.send(publish.into());
+    }
+
+    let status = connection.limit_status();
+    assert_eq!(status.publish_send_inflight, 2);
+    assert!(status.is_publish_send_saturated());
+    assert!(status.max_stored_packet_size > 0);
+    assert!(!status.is_stored_packet_too_large());
+    assert!(!status.is_topic_alias_send_saturated());
+    assert!(!status.is_publish_recv_saturated());
+}
+
+#[test]
+fn limit_status_publish_send_inflight_without_receive_maximum() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    common::v3_1_1_client_establish_connection(&mut connection, true, false);
+
+    for qos in [
+        mqtt::packet::Qos::AtLeastOnce,
+        mqtt::packet::Qos::ExactlyOnce,
+    ] {
+        let packet_id = connection.acquire_packet_id().unwrap();
+        let publish = mqtt::packet::v3_1_1::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .qos(qos)
+            .packet_id(packet_id)
+            .payload(b"payload".to_vec())
+            .build()
+            .unwrap();
+        let _events = connection.send(publish.into());
+    }
+
+    let status = connection.limit_status();
+    assert_eq!(status.publish_send_max, None);
+    assert_eq!(status.publish_send_inflight, 2);
+    assert!(!status.is_publish_send_saturated());
+}
@@ -509,3 +509,1013 @@ fn recv_error_malformed_packet_v5_0_connect_on_connected() {
         _ => panic!("Expected NotifyError event, got {:?}", events[1]),
     }
 }
+
+#[test]
+fn recv_error_malformed_packet_v3_1_1_publish_qos1_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    // PUBLISH QoS1, topic "a", packet_id 0
+    let bytes: Vec<u8> = vec![0x32, 0x05, 0x00, 0x01, b'a', 0x00, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 2);
+    match &events[0] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn recv_error_malformed_packet_v5_0_publish_qos1_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    // PUBLISH QoS1, topic "a", packet_id 0, no properties
+    let bytes: Vec<u8> = vec![0x32, 0x06, 0x00, 0x01, b'a', 0x00, 0x00, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::MalformedPacket)
+                );
+            } else {
+                panic!("Expected V5_0Disconnect packet, got {:?}", packet);
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[1]),
+    }
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_malformed_packet_v3_1_1_puback_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    // PUBACK, packet_id 0
+    let bytes: Vec<u8> = vec![0x40, 0x02, 0x00, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 2);
+    match &events[0] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn recv_error_malformed_packet_v5_0_pubrel_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    // PUBREL, packet_id 0
+    let bytes: Vec<u8> = vec![0x62, 0x02, 0x00, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::MalformedPacket)
+                );
+            } else {
+                panic!("Expected V5_0Disconnect packet, got {:?}", packet);
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[1]),
+    }
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_malformed_packet_v3_1_1_connect_reserved_flag() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    let packet = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .expect("Failed to build Connect packet");
+    let mut bytes = packet.to_continuous_buffer();
+    // fixed header(2) + protocol name(6) + protocol level(1), then connect flags
+    bytes[9] |= 0b0000_0001;
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 2);
+    match &events[0] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_connect_invalid_protocol_name() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    let packet = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .expect("Failed to build Connect packet");
+    let mut bytes = packet.to_continuous_buffer();
+    // fixed header(2) + protocol name length(2), then protocol name
+    bytes[4..8].copy_from_slice(b"XXXX");
+    let events = con.recv(&mut mqtt::common::Cursor::new(bytes.as_slice()));
+
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestClose)));
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::ProtocolError
+        ))
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_connect_invalid_protocol_name() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .expect("Failed to build Connect packet");
+    let mut bytes = packet.to_continuous_buffer();
+    // fixed header(2) + protocol name length(2), then protocol name
+    bytes[4..8].copy_from_slice(b"XXXX");
+    let events = con.recv(&mut mqtt::common::Cursor::new(bytes.as_slice()));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Connack(connack) = packet {
+                assert_eq!(
+                    connack.reason_code(),
+                    mqtt::result_code::ConnectReasonCode::ProtocolError
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_connect_invalid_will_property() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    let bytes: Vec<u8> = vec![
+        0x10, 0x16, // fixed header
+        0x00, 0x04, b'M', b'Q', b'T', b'T', 0x05, // protocol name and version
+        0x06, // connect flags: clean start, will flag
+        0x00, 0x00, // keep alive
+        0x00, // property length
+        0x00, 0x01, b'a', // client id
+        0x02, 0x0B, 0x01, // will props: SubscriptionIdentifier(1)
+        0x00, 0x01, b't', // will topic
+        0x00, 0x00, // will payload
+    ];
+    let events = con.recv(&mut mqtt::common::Cursor::new(bytes.as_slice()));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Connack(connack) = packet {
+                assert_eq!(
+                    connack.reason_code(),
+                    mqtt::result_code::ConnectReasonCode::ProtocolError
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_malformed_packet_v5_0_connect_reserved_flag() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .expect("Failed to build Connect packet");
+    let mut bytes = packet.to_continuous_buffer();
+    // fixed header(2) + protocol name(6) + protocol level(1), then connect flags
+    bytes[9] |= 0b0000_0001;
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Connack(connack) = packet {
+                assert_eq!(
+                    connack.reason_code(),
+                    mqtt::result_code::ConnectReasonCode::MalformedPacket
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {:?}", packet);
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    match &events[1] {
+        mqtt::connection::Event::RequestClose => {}
+        _ => panic!("Expected RequestClose event, got {:?}", events[1]),
+    }
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::MalformedPacket);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v5_0_connect_empty_client_id_required() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_require_client_id(true);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("")
+        .unwrap()
+        .clean_start(true)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Connack(connack) = packet {
+                assert_eq!(
+                    connack.reason_code(),
+                    mqtt::result_code::ConnectReasonCode::ClientIdentifierNotValid
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(
+                *error,
+                mqtt::result_code::MqttError::ClientIdentifierNotValid
+            );
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_connect_client_id_too_long() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_max_client_id_length(Some(23));
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("abcdefghijklmnopqrstuvwxyz0123")
+        .unwrap()
+        .clean_session(true)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V3_1_1Connack(connack) = packet {
+                assert_eq!(
+                    connack.return_code(),
+                    mqtt::result_code::ConnectReturnCode::IdentifierRejected
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::ClientIdentifierNotValid
+        )
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_connect_client_id_too_long() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_max_client_id_length(Some(23));
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("abcdefghijklmnopqrstuvwxyz0123")
+        .unwrap()
+        .clean_start(true)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Connack(connack) = packet {
+                assert_eq!(
+                    connack.reason_code(),
+                    mqtt::result_code::ConnectReasonCode::ClientIdentifierNotValid
+                );
+            } else {
+                panic!("Expected CONNACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(
+            mqtt::result_code::MqttError::ClientIdentifierNotValid
+        )
+    ));
+
+    // A client identifier within the limit is accepted
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_max_client_id_length(Some(23));
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("abcdefghijklmnopqrstuvw")
+        .unwrap()
+        .clean_start(true)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyPacketReceived(_)
+    ));
+}
+
+#[test]
+fn recv_v5_0_connect_empty_client_id_not_required() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_require_client_id(false);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("")
+        .unwrap()
+        .clean_start(true)
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::NotifyPacketReceived(mqtt::packet::Packet::V5_0Connect(
+            connect,
+        )) => {
+            assert_eq!(connect.client_id(), "");
+        }
+        _ => panic!("Expected NotifyPacketReceived event, got {:?}", events[0]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_auth_any() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Any>::new(mqtt::Version::V3_1_1);
+
+    // AUTH packet with remaining length 0
+    let data = [0xF0, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(data.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::ProtocolError);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[0]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_auth_client() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+
+    let data = [0xF0, 0x00];
+    let mut cursor = mqtt::common::Cursor::new(data.as_slice());
+    let events = con.recv(&mut cursor);
+
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::ProtocolError);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[0]),
+    }
+}
+
+#[test]
+fn recv_error_v5_0_reject_dollar_publish_qos1() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_reject_dollar_publish(true);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("$SYS/x")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 1);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Puback(puback) = packet {
+                assert_eq!(puback.packet_id(), 1u16);
+                assert_eq!(
+                    puback.reason_code(),
+                    Some(mqtt::result_code::PubackReasonCode::TopicNameInvalid)
+                );
+            } else {
+                panic!("Expected PUBACK packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+}
+
+#[test]
+fn recv_error_v5_0_reject_dollar_publish_qos0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_reject_dollar_publish(true);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("$SYS/x")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtMostOnce)
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::TopicNameInvalid)
+                );
+            } else {
+                panic!("Expected DISCONNECT packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::TopicNameInvalid);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_reject_dollar_publish() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    con.set_reject_dollar_publish(true);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("$SYS/x")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    match &events[1] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::TopicNameInvalid);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn recv_v5_0_dollar_publish_not_rejected_by_default() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("$SYS/x")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtMostOnce)
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyPacketReceived(_))));
+}
+
+#[test]
+fn recv_error_v5_0_server_puback_with_topic_alias() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(publish);
+
+    // PUBACK must not contain Topic Alias property
+    let data = [
+        0x40, // PUBACK
+        0x07, // remaining length
+        0x00, 0x01, // packet identifier
+        0x00, // reason code: Success
+        0x03, // property length
+        0x23, 0x00, 0x01, // Topic Alias: 1
+    ];
+    let events = con.recv(&mut mqtt::common::Cursor::new(data.as_slice()));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+                );
+            } else {
+                panic!("Expected DISCONNECT packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::ProtocolError);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+fn assert_v5_0_ack_qos_mismatch(events: &[mqtt::connection::Event]) {
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+                );
+            } else {
+                panic!("Expected DISCONNECT packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::AckQosMismatch);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v5_0_puback_for_qos2_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(publish);
+
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_v5_0_ack_qos_mismatch(&events);
+}
+
+#[test]
+fn recv_error_v5_0_pubrec_for_qos1_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(publish);
+
+    let pubrec = mqtt::packet::v5_0::Pubrec::builder()
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let bytes = pubrec.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_v5_0_ack_qos_mismatch(&events);
+}
+
+#[test]
+fn recv_error_v5_0_puback_for_unknown_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(1u16)
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 3);
+    match &events[2] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::ProtocolError);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_duplicate_puback_error_context() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    con.set_error_context(true);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(publish);
+
+    let puback = mqtt::packet::v3_1_1::Puback::builder()
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyErrorContext(_))));
+
+    // The duplicate PUBACK is a protocol error
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+    match &events[2] {
+        mqtt::connection::Event::NotifyErrorContext(context) => {
+            assert_eq!(
+                *context,
+                mqtt::connection::MqttErrorContext {
+                    error: mqtt::result_code::MqttError::ProtocolError,
+                    packet_type: Some(mqtt::packet::PacketType::Puback),
+                    packet_id: Some(packet_id as u32),
+                }
+            );
+        }
+        _ => panic!("Expected NotifyErrorContext event, got {:?}", events[2]),
+    }
+}
+
+#[test]
+fn recv_error_v5_0_publish_error_context() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    con.set_error_context(true);
+    v5_0_server_establish_connection(&mut con);
+
+    // Topic alias is not allowed as TopicAliasMaximum is not sent
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(7u16)
+        .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    let position = events
+        .iter()
+        .position(|e| matches!(e, mqtt::connection::Event::NotifyError(_)))
+        .expect("Expected NotifyError event");
+    match &events[position + 1] {
+        mqtt::connection::Event::NotifyErrorContext(context) => {
+            assert_eq!(context.packet_type, Some(mqtt::packet::PacketType::Publish));
+            assert_eq!(context.packet_id, Some(7));
+        }
+        _ => panic!(
+            "Expected NotifyErrorContext event, got {:?}",
+            events[position + 1]
+        ),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_subscribe_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    let data: Vec<u8> = vec![
+        0x82, 0x06, // fixed header
+        0x00, 0x00, // packet ID: 0
+        0x00, 0x01, b'a', 0x00, // topic filter and QoS
+    ];
+    let events = con.recv(&mut mqtt::common::Cursor::new(data.as_slice()));
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_unsubscribe_packet_id_zero() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let data: Vec<u8> = vec![
+        0xa2, 0x06, // fixed header
+        0x00, 0x00, // packet ID: 0
+        0x00, // property length
+        0x00, 0x01, b'a', // topic filter
+    ];
+    let events = con.recv(&mut mqtt::common::Cursor::new(data.as_slice()));
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+                );
+            } else {
+                panic!("Expected DISCONNECT packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_v3_1_1_pubrec_for_qos1_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let _ = con.checked_send(publish);
+
+    let pubrec = mqtt::packet::v3_1_1::Pubrec::builder()
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let bytes = pubrec.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    match &events[1] {
+        mqtt::connection::Event::NotifyError(error) => {
+            assert_eq!(*error, mqtt::result_code::MqttError::AckQosMismatch);
+        }
+        _ => panic!("Expected NotifyError event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn recv_error_v3_1_1_server_second_connect() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V3_1_1);
+    v3_1_1_server_establish_connection(&mut con, true, false);
+
+    let connect = mqtt::packet::v3_1_1::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 2);
+    assert!(matches!(events[0], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_server_second_connect() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut con);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert_eq!(events.len(), 3);
+    match &events[0] {
+        mqtt::connection::Event::RequestSendPacket { packet, .. } => {
+            if let mqtt::packet::Packet::V5_0Disconnect(disconnect) = packet {
+                assert_eq!(
+                    disconnect.reason_code(),
+                    Some(mqtt::result_code::DisconnectReasonCode::ProtocolError)
+                );
+            } else {
+                panic!("Expected DISCONNECT packet, got {packet:?}");
+            }
+        }
+        _ => panic!("Expected RequestSendPacket event, got {:?}", events[0]),
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    assert!(matches!(
+        events[2],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+}
+
+#[test]
+fn recv_error_v5_0_server_second_connect_before_connack() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_connecting(&mut con);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let bytes = connect.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // DISCONNECT cannot be sent before CONNACK, the connection is just closed
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+    let close_pos = events
+        .iter()
+        .position(|e| matches!(e, mqtt::connection::Event::RequestClose))
+        .expect("Expected RequestClose event");
+    assert!(matches!(
+        events[close_pos + 1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::ProtocolError)
+    ));
+    assert_eq!(close_pos + 2, events.len());
+}
@@ -177,7 +177,7 @@ fn client_recv_pubrel_success_v5_0() {
 }
 
 #[test]
-fn client_recv_pubrel_pid_not_found_v5_0() {
+fn client_recv_pubrel_unknown_pid_pubcomp_success_v5_0() {
     common::init_tracing();
     let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
     connection.set_auto_pub_response(true);
@@ -197,10 +197,7 @@ fn client_recv_pubrel_pid_not_found_v5_0() {
     } = &events[0]
     {
         if let mqtt::packet::Packet::V5_0Pubcomp(pubcomp) = packet {
-            assert_eq!(
-                pubcomp.reason_code().unwrap(),
-                mqtt::result_code::PubcompReasonCode::PacketIdentifierNotFound
-            );
+            assert!(pubcomp.reason_code().is_none());
         } else {
             panic!("Expected V5_0Pubcomp packet, but got: {:?}", packet);
         }
@@ -217,6 +214,47 @@ fn client_recv_pubrel_pid_not_found_v5_0() {
     }
 }
 
+#[test]
+fn client_recv_pubrel_replayed_after_pubcomp_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_auto_pub_response(true);
+    v5_0_client_establish_connection(&mut connection);
+
+    let packet = mqtt::packet::v5_0::Publish::builder()
+        .packet_id(1)
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .payload(b"payload A")
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let pubrel = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(1)
+        .build()
+        .unwrap();
+    let bytes = pubrel.to_continuous_buffer();
+
+    // First PUBREL completes the QoS2 flow, replayed PUBREL must be answered again
+    for _ in 0..2 {
+        let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+        assert_eq!(events.len(), 2);
+        if let mqtt::connection::GenericEvent::RequestSendPacket { packet, .. } = &events[0] {
+            if let mqtt::packet::Packet::V5_0Pubcomp(pubcomp) = packet {
+                assert_eq!(pubcomp.packet_id(), 1);
+                assert!(pubcomp.reason_code().is_none());
+            } else {
+                panic!("Expected V5_0Pubcomp packet, but got: {:?}", packet);
+            }
+        } else {
+            panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+        }
+    }
+}
+
 #[test]
 fn client_recv_pingresp_v5_0() {
     common::init_tracing();
@@ -265,6 +303,88 @@ fn server_recv_disconnect_v5_0() {
     }
 }
 
+#[test]
+fn client_recv_disconnect_client_only_reason_code_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_notify_warning(true);
+    v5_0_client_establish_connection(&mut connection);
+
+    // DisconnectWithWillMessage can only be sent by a client
+    let packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::DisconnectWithWillMessage)
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyWarning(
+            mqtt::result_code::MqttError::DisconnectReasonCodeNotAllowed
+        )
+    ));
+    match &events[1] {
+        mqtt::connection::Event::NotifyPacketReceived(evt_packet) => {
+            assert_eq!(*evt_packet, packet.into());
+        }
+        _ => panic!("Expected NotifyPacketReceived event, got {:?}", events[1]),
+    }
+}
+
+#[test]
+fn server_recv_disconnect_server_only_reason_code_v5_0() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    connection.set_notify_warning(true);
+    v5_0_server_establish_connection(&mut connection);
+
+    let packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyWarning(
+            mqtt::result_code::MqttError::DisconnectReasonCodeNotAllowed
+        )
+    ));
+
+    // A reason code allowed for clients is not flagged
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    connection.set_notify_warning(true);
+    v5_0_server_establish_connection(&mut connection);
+    let packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::DisconnectWithWillMessage)
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyPacketReceived(_)
+    ));
+
+    // NotifyWarning is not emitted unless enabled
+    let mut connection = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    v5_0_server_establish_connection(&mut connection);
+    let packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .build()
+        .unwrap();
+    let bytes = packet.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyPacketReceived(_)
+    ));
+}
+
 #[test]
 fn server_recv_auth_v5_0() {
     common::init_tracing();
@@ -340,3 +460,79 @@ fn client_recv_publish_qos0_v3_1_1_edge_remaining_length() {
         }
     }
 }
+
+#[test]
+fn client_recv_multiple_packets_limited_per_recv() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+    con.set_max_packets_per_recv(Some(2));
+
+    let mut bytes = Vec::new();
+    for i in 0..5u8 {
+        let publish = mqtt::packet::v3_1_1::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .qos(mqtt::packet::Qos::AtMostOnce)
+            .payload(vec![i])
+            .build()
+            .unwrap();
+        bytes.extend(publish.to_continuous_buffer());
+    }
+    let packet_size = bytes.len() / 5;
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+
+    let received = |events: &[mqtt::connection::Event]| -> Vec<u8> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                mqtt::connection::Event::NotifyPacketReceived(
+                    mqtt::packet::Packet::V3_1_1Publish(p),
+                ) => Some(p.payload().as_slice()[0]),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let events = con.recv(&mut cursor);
+    assert_eq!(received(&events), vec![0, 1]);
+    assert_eq!(cursor.position() as usize, packet_size * 2);
+
+    let events = con.recv(&mut cursor);
+    assert_eq!(received(&events), vec![2, 3]);
+    assert_eq!(cursor.position() as usize, packet_size * 4);
+
+    // Without a limit, all remaining packets are processed
+    con.set_max_packets_per_recv(None);
+    let events = con.recv(&mut cursor);
+    assert_eq!(received(&events), vec![4]);
+    assert_eq!(cursor.position() as usize, bytes.len());
+}
+
+#[test]
+fn client_recv_multiple_packets_stops_when_disconnected() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+
+    // The client has sent DISCONNECT, the server's packets are still arriving
+    let disconnect = mqtt::packet::v3_1_1::Disconnect::builder().build().unwrap();
+    let _ = con.send(disconnect.into());
+
+    let mut bytes = Vec::new();
+    for i in 0..2u8 {
+        let publish = mqtt::packet::v3_1_1::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .qos(mqtt::packet::Qos::AtMostOnce)
+            .payload(vec![i])
+            .build()
+            .unwrap();
+        bytes.extend(publish.to_continuous_buffer());
+    }
+    let packet_size = bytes.len() / 2;
+    let mut cursor = mqtt::common::Cursor::new(bytes.as_slice());
+
+    let _events = con.recv(&mut cursor);
+    assert_eq!(cursor.position() as usize, packet_size);
+}
@@ -180,6 +180,83 @@ fn v3_1_1_client_not_allowed_to_send_invalid_pid_pubrel() {
     }
 }
 
+#[test]
+fn v3_1_1_client_not_allowed_to_send_pubrel_before_pubrec() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish: mqtt::packet::Packet = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Publish packet")
+        .into();
+    let _events = con.send(publish);
+
+    // PUBREC has not been received yet
+    let packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    let events = con.send(packet);
+    assert_eq!(events.len(), 1);
+
+    if let mqtt::connection::Event::NotifyError(error) = &events[0] {
+        assert_eq!(error, &mqtt::result_code::MqttError::PubrecNotReceived);
+    } else {
+        assert!(
+            false,
+            "Expected NotifyError event, but got: {:?}",
+            events[0]
+        );
+    }
+
+    // After PUBREC is received, PUBREL can be sent
+    let pubrec = mqtt::packet::v3_1_1::Pubrec::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    let events = con.send(packet);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+
+    // PUBREL for a QoS 1 flow is rejected as well
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish: mqtt::packet::Packet = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Publish packet")
+        .into();
+    let _events = con.send(publish);
+    let packet: mqtt::packet::Packet = mqtt::packet::v3_1_1::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    let events = con.send(packet);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PubrecNotReceived)
+    ));
+}
+
 // connected
 
 #[test]
@@ -1363,6 +1440,87 @@ fn v5_0_client_not_allowed_to_send_invalid_pid_pubrel() {
     }
 }
 
+#[test]
+fn v5_0_client_not_allowed_to_send_pubrel_before_pubrec() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut con);
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Publish packet")
+        .into();
+    let _events = con.send(publish);
+
+    // PUBREC has not been received yet
+    let packet: mqtt::packet::Packet = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    assert_eq!(
+        con.send_rejections(&packet),
+        vec![mqtt::result_code::MqttError::PubrecNotReceived]
+    );
+    let events = con.send(packet);
+    assert_eq!(events.len(), 1);
+
+    if let mqtt::connection::Event::NotifyError(error) = &events[0] {
+        assert_eq!(error, &mqtt::result_code::MqttError::PubrecNotReceived);
+    } else {
+        assert!(
+            false,
+            "Expected NotifyError event, but got: {:?}",
+            events[0]
+        );
+    }
+
+    // After PUBREC is received, PUBREL can be sent
+    let pubrec = mqtt::packet::v5_0::Pubrec::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let packet: mqtt::packet::Packet = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    let events = con.send(packet);
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::RequestSendPacket { .. })));
+
+    // PUBREL for a QoS 1 flow is rejected as well
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish: mqtt::packet::Packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Publish packet")
+        .into();
+    let _events = con.send(publish);
+    let packet: mqtt::packet::Packet = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet")
+        .into();
+    let events = con.send(packet);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PubrecNotReceived)
+    ));
+}
+
 // connected
 
 #[test]
@@ -2496,3 +2654,63 @@ fn v5_0_server_not_allowed_to_send_on_status_disconnected_auth() {
 ///////////////////////////////////////////////////////////////////////////////
 
 ///////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn v5_0_server_not_allowed_to_send_auth_without_auth_method_in_connect() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    // CONNECT without AuthenticationMethod
+    v5_0_server_connecting(&mut con);
+
+    let packet: mqtt::packet::Packet = mqtt::packet::v5_0::Auth::builder()
+        .reason_code(mqtt::result_code::AuthReasonCode::ContinueAuthentication)
+        .props(vec![mqtt::packet::AuthenticationMethod::new("SCRAM-SHA-1")
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Auth packet")
+        .into();
+    let events = con.send(packet);
+    assert_eq!(events.len(), 1);
+    if let mqtt::connection::Event::NotifyError(error) = &events[0] {
+        assert_eq!(error, &mqtt::result_code::MqttError::PacketNotAllowedToSend);
+    } else {
+        panic!("Expected NotifyError event, but got: {:?}", events[0]);
+    }
+}
+
+#[test]
+fn v5_0_server_send_auth_with_auth_method_in_connect() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Server>::new(mqtt::Version::V5_0);
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::AuthenticationMethod::new("SCRAM-SHA-1")
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let bytes = connect.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let packet: mqtt::packet::Packet = mqtt::packet::v5_0::Auth::builder()
+        .reason_code(mqtt::result_code::AuthReasonCode::ContinueAuthentication)
+        .props(vec![mqtt::packet::AuthenticationMethod::new("SCRAM-SHA-1")
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Auth packet")
+        .into();
+    let events = con.send(packet.clone());
+    assert_eq!(events.len(), 1);
+    if let mqtt::connection::Event::RequestSendPacket {
+        packet: sent_packet,
+        ..
+    } = &events[0]
+    {
+        assert_eq!(*sent_packet, packet);
+    } else {
+        panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+    }
+}
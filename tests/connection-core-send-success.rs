@@ -1036,3 +1036,125 @@ fn v5_0_client_send_connect_keep_alive() {
         );
     }
 }
+
+#[test]
+fn v5_0_client_publish_default_qos1() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut con);
+
+    con.set_default_publish_qos(mqtt::packet::Qos::AtLeastOnce);
+    con.set_default_publish_retain(true);
+    let events = con.publish_default("test/topic", "payload");
+    assert_eq!(events.len(), 1);
+    let packet_id = if let mqtt::connection::Event::RequestSendPacket {
+        packet,
+        release_packet_id_if_send_error,
+    } = &events[0]
+    {
+        if let mqtt::packet::Packet::V5_0Publish(publish) = packet {
+            assert_eq!(publish.topic_name(), "test/topic");
+            assert_eq!(publish.qos(), mqtt::packet::Qos::AtLeastOnce);
+            assert!(publish.retain());
+            assert_eq!(publish.payload().as_slice(), b"payload");
+            assert_eq!(*release_packet_id_if_send_error, publish.packet_id());
+            publish.packet_id().unwrap()
+        } else {
+            panic!("Expected V5_0Publish packet, but got: {:?}", packet);
+        }
+    } else {
+        panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+    };
+
+    // The packet ID is tracked until PUBACK is received
+    assert_eq!(
+        con.register_packet_id(packet_id),
+        Err(mqtt::result_code::MqttError::PacketIdentifierConflict)
+    );
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(packet_id)
+        .build()
+        .unwrap();
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert!(events.iter().any(
+        |e| matches!(e, mqtt::connection::Event::NotifyPacketIdReleased(id) if *id == packet_id)
+    ));
+}
+
+#[test]
+fn v3_1_1_client_publish_default_qos0() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    v3_1_1_client_establish_connection(&mut con, true, false);
+
+    let events = con.publish_default("test/topic", "payload");
+    assert_eq!(events.len(), 1);
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        if let mqtt::packet::Packet::V3_1_1Publish(publish) = packet {
+            assert_eq!(publish.qos(), mqtt::packet::Qos::AtMostOnce);
+            assert!(!publish.retain());
+            assert_eq!(publish.packet_id(), None);
+        } else {
+            panic!("Expected V3_1_1Publish packet, but got: {:?}", packet);
+        }
+    } else {
+        panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+    }
+}
+
+#[test]
+fn v5_0_client_publish_default_invalid_topic() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_establish_connection(&mut con);
+
+    con.set_default_publish_qos(mqtt::packet::Qos::ExactlyOnce);
+    let events = con.publish_default("test/#", "payload");
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyPacketIdReleased(_)
+    ));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::MalformedPacket)
+    ));
+}
+
+#[test]
+fn v5_0_client_publish_default_packet_too_large_releases_packet_id() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(20)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    con.set_default_publish_qos(mqtt::packet::Qos::AtLeastOnce);
+    let events = con.publish_default("test/topic", "0123456789abcdefghij");
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyPacketIdReleased(1)
+    ));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketTooLarge)
+    ));
+
+    // The released packet ID is reused
+    assert_eq!(con.acquire_packet_id(), Ok(1));
+}
@@ -764,3 +764,387 @@ fn v5_0_send_stored_success_server() {
         panic!("Expected RequestSendPacket event, got: {:?}", events[2]);
     }
 }
+
+#[test]
+fn stored_publishes_and_pubrels() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    common::v3_1_1_client_establish_connection(&mut con, false, false);
+
+    let pid_q1 = con.acquire_packet_id().unwrap();
+    let pub_q1 = mqtt::packet::v3_1_1::Publish::builder()
+        .packet_id(pid_q1)
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload_a")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(pub_q1);
+
+    let pid_q2 = con.acquire_packet_id().unwrap();
+    let pub_q2 = mqtt::packet::v3_1_1::Publish::builder()
+        .packet_id(pid_q2)
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload_b")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(pub_q2);
+
+    let pubrec = mqtt::packet::v3_1_1::Pubrec::builder()
+        .packet_id(pid_q2)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let pubrel = mqtt::packet::v3_1_1::Pubrel::builder()
+        .packet_id(pid_q2)
+        .build()
+        .expect("Failed to build Pubrel packet");
+    let _ = con.checked_send(pubrel);
+
+    assert_eq!(con.get_stored_packets().len(), 2);
+
+    let publishes = con.stored_publishes();
+    assert_eq!(publishes.len(), 1);
+    match &publishes[0] {
+        mqtt::packet::GenericStorePacket::V3_1_1Publish(p) => {
+            assert_eq!(p.packet_id(), Some(pid_q1));
+        }
+        other => panic!("Expected V3_1_1Publish, got: {other:?}"),
+    }
+
+    let pubrels = con.stored_pubrels();
+    assert_eq!(pubrels.len(), 1);
+    match &pubrels[0] {
+        mqtt::packet::GenericStorePacket::V3_1_1Pubrel(p) => {
+            assert_eq!(p.packet_id(), pid_q2);
+        }
+        other => panic!("Expected V3_1_1Pubrel, got: {other:?}"),
+    }
+}
+
+#[test]
+fn resend_stored_packet() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    common::v3_1_1_client_establish_connection(&mut con, false, false);
+
+    let pid_a = con.acquire_packet_id().unwrap();
+    let pub_a = mqtt::packet::v3_1_1::Publish::builder()
+        .packet_id(pid_a)
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload_a")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(pub_a);
+
+    let pid_b = con.acquire_packet_id().unwrap();
+    let pub_b = mqtt::packet::v3_1_1::Publish::builder()
+        .packet_id(pid_b)
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload_b")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(pub_b);
+
+    let events = con.resend_stored_packet(pid_b);
+    assert_eq!(events.len(), 1);
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        if let mqtt::packet::GenericPacket::V3_1_1Publish(publish) = packet {
+            assert_eq!(publish.packet_id(), Some(pid_b));
+            assert!(publish.dup());
+            assert_eq!(publish.payload().as_slice(), b"payload_b");
+        } else {
+            panic!("Expected V3_1_1Publish packet, got: {packet:?}");
+        }
+    } else {
+        panic!("Expected RequestSendPacket event, got: {:?}", events[0]);
+    }
+
+    // The packet is still stored
+    assert_eq!(con.get_stored_packets().len(), 2);
+
+    // Unknown packet id
+    let events = con.resend_stored_packet(pid_b + 1);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketIdentifierInvalid)
+    ));
+}
+
+#[test]
+fn resend_stored_packet_resets_pingreq_send_timer() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V3_1_1);
+    common::v3_1_1_client_establish_connection(&mut con, false, false);
+    let _ = con.set_pingreq_send_interval(Some(10000));
+
+    let pid = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .packet_id(pid)
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(publish);
+
+    let events = con.resend_stored_packet(pid);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::RequestSendPacket { .. }
+    ));
+    assert!(matches!(
+        events[1],
+        mqtt::connection::Event::RequestTimerReset {
+            kind: mqtt::connection::TimerKind::PingreqSend,
+            duration_ms: 10000
+        }
+    ));
+}
+
+#[test]
+fn resend_stored_packet_oversize() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(0xffffffff)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let _ = con.checked_send(connect);
+
+    // CONNACK with MaximumPacketSize 30
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::MaximumPacketSize::new(30)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    // Restore a packet that exceeds the peer's MaximumPacketSize
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .packet_id(1u16)
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("0123456789012345678901234567890123456789")
+        .build()
+        .unwrap();
+    con.restore_packets(vec![publish.try_into().unwrap()]);
+
+    let events = con.resend_stored_packet(1);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::PacketTooLarge)
+    ));
+}
+
+#[test]
+fn notify_store_empty_after_puback() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_notify_store_empty(true);
+
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(0xffffffff)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _ = con.checked_send(packet);
+
+    let packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .expect("Failed to build Connack packet");
+    let bytes = packet.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .packet_id(packet_id)
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(publish);
+    assert_eq!(con.get_stored_packets().len(), 1);
+
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Puback packet");
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(con.get_stored_packets().is_empty());
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyStoreEmpty)
+    ));
+}
+
+fn v5_0_client_connect_with_session(con: &mut mqtt::Connection<mqtt::role::Client>) {
+    let packet = mqtt::packet::v5_0::Connect::builder()
+        .client_id("cid1")
+        .unwrap()
+        .props(vec![mqtt::packet::SessionExpiryInterval::new(0xffffffff)
+            .unwrap()
+            .into()])
+        .build()
+        .expect("Failed to build Connect packet");
+    let _ = con.checked_send(packet);
+
+    let packet = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .expect("Failed to build Connack packet");
+    let bytes = packet.to_continuous_buffer();
+    let _ = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+}
+
+fn v5_0_client_send_publish(
+    con: &mut mqtt::Connection<mqtt::role::Client>,
+    qos: mqtt::packet::Qos,
+) -> u16 {
+    let packet_id = con.acquire_packet_id().unwrap();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .packet_id(packet_id)
+        .qos(qos)
+        .topic_name("t")
+        .unwrap()
+        .payload("payload")
+        .build()
+        .expect("Failed to build Publish packet");
+    let _ = con.checked_send(publish);
+    packet_id
+}
+
+#[test]
+fn notify_store_empty_disabled_by_default() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_session(&mut con);
+    let packet_id = v5_0_client_send_publish(&mut con, mqtt::packet::Qos::AtLeastOnce);
+
+    let puback = mqtt::packet::v5_0::Puback::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Puback packet");
+    let bytes = puback.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(con.get_stored_packets().is_empty());
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyStoreEmpty)));
+}
+
+#[test]
+fn notify_store_empty_not_emitted_after_successful_pubrec() {
+    common::init_tracing();
+    let mut con = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    con.set_notify_store_empty(true);
+    v5_0_client_connect_with_session(&mut con);
+    let packet_id = v5_0_client_send_publish(&mut con, mqtt::packet::Qos::ExactlyOnce);
+
+    // PUBREL is not sent automatically, so the store is empty after PUBREC
+    let pubrec = mqtt::packet::v5_0::Pubrec::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrec packet");
+    let bytes = pubrec.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(con.get_stored_packets().is_empty());
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, mqtt::connection::Event::NotifyStoreEmpty)));
+
+    let pubrel = mqtt::packet::v5_0::Pubrel::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubrel packet");
+    let _ = con.checked_send(pubrel);
+    assert_eq!(con.get_stored_packets().len(), 1);
+
+    let pubcomp = mqtt::packet::v5_0::Pubcomp::builder()
+        .packet_id(packet_id)
+        .build()
+        .expect("Failed to build Pubcomp packet");
+    let bytes = pubcomp.to_continuous_buffer();
+    let events = con.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+    assert!(con.get_stored_packets().is_empty());
+    assert!(matches!(
+        events.last(),
+        Some(mqtt::connection::Event::NotifyStoreEmpty)
+    ));
+}
+
+#[test]
+fn export_import_packet_id_ranges_v5_0() {
+    common::init_tracing();
+    let mut exporter = mqtt::GenericConnection::<mqtt::role::Client, u32>::new(mqtt::Version::V5_0);
+    for packet_id in [1u32, 2, 3, 10, 100_000] {
+        exporter.register_packet_id(packet_id).unwrap();
+    }
+    let ranges = exporter.export_packet_id_ranges();
+    assert_eq!(ranges, vec![(1, 3), (10, 10), (100_000, 100_000)]);
+
+    let mut importer = mqtt::GenericConnection::<mqtt::role::Client, u32>::new(mqtt::Version::V5_0);
+    let publish = mqtt::packet::v5_0::GenericPublish::<u32>::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(20u32)
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    importer.restore_packets(vec![mqtt::packet::GenericStorePacket::V5_0Publish(publish)]);
+    importer.import_packet_id_ranges(&ranges).unwrap();
+
+    // The packet ID of the stored packet stays in use
+    assert_eq!(
+        importer.export_packet_id_ranges(),
+        vec![(1, 3), (10, 10), (20, 20), (100_000, 100_000)]
+    );
+    assert_eq!(importer.acquire_packet_id().unwrap(), 4);
+
+    // Invalid ranges leave the packet IDs in use unchanged
+    assert_eq!(
+        importer.import_packet_id_ranges(&[(5, 8), (7, 9)]),
+        Err(mqtt::result_code::MqttError::PacketIdentifierConflict)
+    );
+    assert_eq!(
+        importer.export_packet_id_ranges(),
+        vec![(1, 4), (10, 10), (20, 20), (100_000, 100_000)]
+    );
+}
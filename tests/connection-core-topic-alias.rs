@@ -1327,3 +1327,458 @@ fn regulate_for_store_topic_alias() {
 //         .unwrap();
 
 // }
+
+#[test]
+fn clear_topic_alias_recv() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    {
+        // Send CONNECT
+        let connect = mqtt::packet::v5_0::Connect::builder()
+            .client_id("test_client")
+            .unwrap()
+            .props(vec![mqtt::packet::TopicAliasMaximum::new(3)
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap();
+
+        let _events = connection.send(connect.into());
+
+        let connack = mqtt::packet::v5_0::Connack::builder()
+            .session_present(false)
+            .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+            .build()
+            .unwrap();
+
+        let bytes = connack.to_continuous_buffer();
+        let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    }
+
+    {
+        // Recv QoS0 PUBLISH A with topic alias register
+        let publish_a = mqtt::packet::v5_0::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .payload(b"payload A".to_vec())
+            .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+            .build()
+            .unwrap();
+
+        let bytes = publish_a.to_continuous_buffer();
+        let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+        assert_eq!(events.len(), 1);
+    }
+
+    connection.clear_topic_alias_recv();
+
+    {
+        // Recv QoS0 PUBLISH B with the cleared topic alias
+        let publish_b = mqtt::packet::v5_0::Publish::builder()
+            .payload(b"payload B".to_vec())
+            .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+            .build()
+            .unwrap();
+
+        let bytes = publish_b.to_continuous_buffer();
+        let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+
+        assert_eq!(events.len(), 3);
+        let expected_disconnect: mqtt::packet::Packet = mqtt::packet::v5_0::Disconnect::builder()
+            .reason_code(mqtt::result_code::DisconnectReasonCode::TopicAliasInvalid)
+            .build()
+            .unwrap()
+            .into();
+        if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+            assert_eq!(*packet, expected_disconnect);
+        } else {
+            panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+        }
+        assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+        assert!(matches!(
+            events[2],
+            mqtt::connection::Event::NotifyError(mqtt::result_code::MqttError::TopicAliasInvalid)
+        ));
+    }
+}
+
+#[test]
+fn clear_topic_alias_send() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+
+    {
+        let connect = mqtt::packet::v5_0::Connect::builder()
+            .client_id("test_client")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let _events = connection.send(connect.into());
+
+        // Receive CONNACK with TopicAliasMaximum set to 3
+        let connack = mqtt::packet::v5_0::Connack::builder()
+            .session_present(false)
+            .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+            .props(vec![mqtt::packet::TopicAliasMaximum::new(3)
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap();
+
+        let bytes = connack.to_continuous_buffer();
+        let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    }
+
+    {
+        // Send QoS0 PUBLISH A with topic alias register
+        let publish_a = mqtt::packet::v5_0::Publish::builder()
+            .topic_name("topic/a")
+            .unwrap()
+            .payload(b"payload A".to_vec())
+            .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+            .build()
+            .unwrap();
+
+        let events = connection.send(publish_a.into());
+        assert_eq!(events.len(), 1);
+    }
+
+    connection.clear_topic_alias_send();
+
+    {
+        // Send QoS0 PUBLISH B with the cleared topic alias
+        let publish_b = mqtt::packet::v5_0::Publish::builder()
+            .payload(b"payload B".to_vec())
+            .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+            .build()
+            .unwrap();
+
+        let events = connection.send(publish_b.into());
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            mqtt::connection::Event::NotifyError(
+                mqtt::result_code::MqttError::PacketNotAllowedToSend
+            )
+        ));
+    }
+}
+
+#[test]
+fn topic_alias_send_len() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    assert_eq!(connection.topic_alias_send_len(), 0);
+
+    {
+        let connect = mqtt::packet::v5_0::Connect::builder()
+            .client_id("test_client")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let _events = connection.send(connect.into());
+
+        // Receive CONNACK with TopicAliasMaximum set to 5
+        let connack = mqtt::packet::v5_0::Connack::builder()
+            .session_present(false)
+            .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+            .props(vec![mqtt::packet::TopicAliasMaximum::new(5)
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap();
+
+        let bytes = connack.to_continuous_buffer();
+        let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    }
+
+    for (alias, topic) in [(1, "topic/a"), (2, "topic/b"), (3, "topic/c")] {
+        let publish = mqtt::packet::v5_0::Publish::builder()
+            .topic_name(topic)
+            .unwrap()
+            .payload(b"payload".to_vec())
+            .props(vec![mqtt::packet::TopicAlias::new(alias).unwrap().into()])
+            .build()
+            .unwrap();
+
+        let events = connection.send(publish.into());
+        assert_eq!(events.len(), 1);
+    }
+
+    assert_eq!(connection.topic_alias_send_len(), 3);
+    assert_eq!(connection.topic_alias_recv_len(), 0);
+
+    connection.clear_topic_alias_send();
+    assert_eq!(connection.topic_alias_send_len(), 0);
+}
+
+#[test]
+fn publish_encoded_size_with_alias_auto_map() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_auto_map_topic_alias_send(true);
+
+    {
+        let connect = mqtt::packet::v5_0::Connect::builder()
+            .client_id("test_client")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let _events = connection.send(connect.into());
+
+        // Receive CONNACK with TopicAliasMaximum set to 2
+        let connack = mqtt::packet::v5_0::Connack::builder()
+            .session_present(false)
+            .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+            .props(vec![mqtt::packet::TopicAliasMaximum::new(2)
+                .unwrap()
+                .into()])
+            .build()
+            .unwrap();
+
+        let bytes = connack.to_continuous_buffer();
+        let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    }
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("a/very/long/topic/name/used/for/topic/alias/size/comparison")
+        .unwrap()
+        .payload(b"payload".to_vec())
+        .build()
+        .unwrap();
+    let verbatim_size = publish.size();
+
+    // Not registered yet, the first PUBLISH carries both the topic and a new alias
+    assert!(connection.publish_encoded_size_with_alias(&publish) > verbatim_size);
+    assert_eq!(connection.topic_alias_send_len(), 0);
+
+    let events = connection.send(publish.clone().into());
+    assert_eq!(events.len(), 1);
+
+    // Registered, the topic is replaced with the alias
+    let aliased_size = connection.publish_encoded_size_with_alias(&publish);
+    assert!(aliased_size < verbatim_size);
+    let expected_size = publish.clone().remove_topic_add_topic_alias(1).size();
+    assert_eq!(aliased_size, expected_size);
+}
+
+fn v5_0_client_connect_with_topic_alias_recv(
+    connection: &mut mqtt::Connection<mqtt::role::Client>,
+    topic_alias_maximum: Option<u16>,
+) {
+    let props = match topic_alias_maximum {
+        Some(max) => vec![mqtt::packet::TopicAliasMaximum::new(max).unwrap().into()],
+        None => vec![],
+    };
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .props(props)
+        .build()
+        .unwrap();
+    let _events = connection.send(connect.into());
+
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+}
+
+fn assert_disconnect_with_error(
+    events: &[mqtt::connection::Event],
+    reason_code: mqtt::result_code::DisconnectReasonCode,
+    error: mqtt::result_code::MqttError,
+) {
+    assert_eq!(events.len(), 3);
+    let expected_disconnect: mqtt::packet::Packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(reason_code)
+        .build()
+        .unwrap()
+        .into();
+    if let mqtt::connection::Event::RequestSendPacket { packet, .. } = &events[0] {
+        assert_eq!(*packet, expected_disconnect);
+    } else {
+        panic!("Expected RequestSendPacket event, but got: {:?}", events[0]);
+    }
+    assert!(matches!(events[1], mqtt::connection::Event::RequestClose));
+    if let mqtt::connection::Event::NotifyError(e) = &events[2] {
+        assert_eq!(*e, error);
+    } else {
+        panic!("Expected NotifyError event, but got: {:?}", events[2]);
+    }
+}
+
+fn v5_0_publish_with_topic_alias_bytes(topic: &str, alias: u16) -> Vec<u8> {
+    mqtt::packet::v5_0::Publish::builder()
+        .topic_name(topic)
+        .unwrap()
+        .payload(b"payload".to_vec())
+        .props(vec![mqtt::packet::TopicAlias::new(alias).unwrap().into()])
+        .build()
+        .unwrap()
+        .to_continuous_buffer()
+}
+
+#[test]
+fn strict_topic_alias_remap_recv() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_strict_topic_alias_remap(true);
+    v5_0_client_connect_with_topic_alias_recv(&mut connection, Some(10));
+
+    // Register alias 4 for "x", then repeat the same mapping
+    for _ in 0..2 {
+        let bytes = v5_0_publish_with_topic_alias_bytes("x", 4);
+        let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            mqtt::connection::Event::NotifyPacketReceived(_)
+        ));
+    }
+
+    // Remap alias 4 to "y"
+    let bytes = v5_0_publish_with_topic_alias_bytes("y", 4);
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_disconnect_with_error(
+        &events,
+        mqtt::result_code::DisconnectReasonCode::TopicAliasInvalid,
+        mqtt::result_code::MqttError::TopicAliasInvalid,
+    );
+}
+
+#[test]
+fn topic_alias_remap_recv_allowed_by_default() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_topic_alias_recv(&mut connection, Some(10));
+
+    for topic in ["x", "y"] {
+        let bytes = v5_0_publish_with_topic_alias_bytes(topic, 4);
+        let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            events[0],
+            mqtt::connection::Event::NotifyPacketReceived(_)
+        ));
+    }
+}
+
+#[test]
+fn empty_topic_recv_topic_alias_disabled() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_topic_alias_recv(&mut connection, None);
+
+    // Empty topic with TopicAlias, but topic alias is not enabled
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .payload(b"payload".to_vec())
+        .props(vec![mqtt::packet::TopicAlias::new(1).unwrap().into()])
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_disconnect_with_error(
+        &events,
+        mqtt::result_code::DisconnectReasonCode::ProtocolError,
+        mqtt::result_code::MqttError::ProtocolError,
+    );
+}
+
+#[test]
+fn empty_topic_no_prop_recv_topic_alias_disabled() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_topic_alias_recv(&mut connection, None);
+
+    // Empty topic without TopicAlias (the builder refuses to build it)
+    let bytes = [
+        0x30, // PUBLISH QoS0
+        0x03, // remaining length
+        0x00, 0x00, // topic name length: 0
+        0x00, // property length
+    ];
+    let events = connection.recv(&mut mqtt::common::Cursor::new(bytes.as_slice()));
+    assert_disconnect_with_error(
+        &events,
+        mqtt::result_code::DisconnectReasonCode::ProtocolError,
+        mqtt::result_code::MqttError::ProtocolError,
+    );
+}
+
+#[test]
+fn empty_topic_recv_topic_alias_enabled_unknown_alias() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    v5_0_client_connect_with_topic_alias_recv(&mut connection, Some(3));
+
+    // Empty topic with an unregistered TopicAlias
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .payload(b"payload".to_vec())
+        .props(vec![mqtt::packet::TopicAlias::new(2).unwrap().into()])
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+    let events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_disconnect_with_error(
+        &events,
+        mqtt::result_code::DisconnectReasonCode::TopicAliasInvalid,
+        mqtt::result_code::MqttError::TopicAliasInvalid,
+    );
+}
+
+#[test]
+fn auto_map_topic_alias_send_eviction_count() {
+    common::init_tracing();
+    let mut connection = mqtt::Connection::<mqtt::role::Client>::new(mqtt::Version::V5_0);
+    connection.set_auto_map_topic_alias_send(true);
+
+    let connect = mqtt::packet::v5_0::Connect::builder()
+        .client_id("test_client")
+        .unwrap()
+        .build()
+        .unwrap();
+    let _events = connection.send(connect.into());
+
+    // Receive CONNACK with TopicAliasMaximum set to 2
+    let connack = mqtt::packet::v5_0::Connack::builder()
+        .session_present(false)
+        .reason_code(mqtt::result_code::ConnectReasonCode::Success)
+        .props(vec![mqtt::packet::TopicAliasMaximum::new(2)
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+    let bytes = connack.to_continuous_buffer();
+    let _events = connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(connection.topic_alias_eviction_count(), 0);
+
+    let mut publish = |topic: &str| {
+        let packet = mqtt::packet::v5_0::Publish::builder()
+            .topic_name(topic)
+            .unwrap()
+            .payload(b"payload".to_vec())
+            .build()
+            .unwrap();
+        let events = connection.send(packet.into());
+        assert_eq!(events.len(), 1);
+    };
+
+    // Fill the alias table
+    publish("topic/a");
+    publish("topic/b");
+    // Already mapped topic doesn't evict
+    publish("topic/a");
+    // New topics overwrite the LRU aliases
+    publish("topic/c");
+    publish("topic/d");
+
+    assert_eq!(connection.topic_alias_eviction_count(), 2);
+}
@@ -360,6 +360,25 @@ fn test_event_serialize_notify_error() {
     assert!(json.contains("MalformedPacket"));
 }
 
+#[test]
+fn test_event_serialize_notify_error_context() {
+    common::init_tracing();
+    use mqtt::connection::Event;
+    use mqtt::result_code::MqttError;
+
+    let event = Event::NotifyErrorContext(mqtt::connection::MqttErrorContext {
+        error: MqttError::ProtocolError,
+        packet_type: Some(mqtt::packet::PacketType::Puback),
+        packet_id: Some(1),
+    });
+
+    let json = serde_json::to_string(&event).unwrap();
+    assert!(json.contains("\"type\":\"notify_error_context\""));
+    assert!(json.contains("\"error\":\"ProtocolError\""));
+    assert!(json.contains("\"packet_type\":\"puback\""));
+    assert!(json.contains("\"packet_id\":1"));
+}
+
 #[test]
 fn test_event_serialize_request_close() {
     common::init_tracing();
@@ -239,6 +239,54 @@ fn test_malformed_remaining_length() {
     }
 }
 
+#[test]
+fn test_malformed_remaining_length_fifth_byte() {
+    common::init_tracing();
+    // Continuation bit set on the 4th byte, followed by a 5th byte
+    let malformed_bytes = [0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+
+    let mut cursor = mqtt::common::Cursor::new(&malformed_bytes[..]);
+    let mut builder = mqtt::connection::PacketBuilder::new();
+
+    match builder.feed(&mut cursor) {
+        mqtt::connection::PacketBuildResult::Error(
+            mqtt::result_code::MqttError::MalformedPacket,
+        ) => (),
+        _ => panic!("Expected MalformedPacket error"),
+    }
+
+    // Same bytes fed one at a time
+    let mut builder = mqtt::connection::PacketBuilder::new();
+    for (i, byte) in malformed_bytes.iter().enumerate() {
+        let data = [*byte];
+        let mut cursor = mqtt::common::Cursor::new(&data[..]);
+        match builder.feed(&mut cursor) {
+            mqtt::connection::PacketBuildResult::Incomplete if i < 4 => (),
+            mqtt::connection::PacketBuildResult::Error(
+                mqtt::result_code::MqttError::MalformedPacket,
+            ) if i == 4 => break,
+            _ => panic!("Unexpected result at byte {i}"),
+        }
+    }
+}
+
+#[test]
+fn test_malformed_remaining_length_overlong() {
+    common::init_tracing();
+    // Remaining Length 0 and 1 must be encoded as a single byte
+    for malformed_bytes in [[0x30, 0x80, 0x00], [0x30, 0x81, 0x00]] {
+        let mut cursor = mqtt::common::Cursor::new(&malformed_bytes[..]);
+        let mut builder = mqtt::connection::PacketBuilder::new();
+
+        match builder.feed(&mut cursor) {
+            mqtt::connection::PacketBuildResult::Error(
+                mqtt::result_code::MqttError::MalformedPacket,
+            ) => (),
+            _ => panic!("Expected MalformedPacket error"),
+        }
+    }
+}
+
 #[test]
 fn test_fragmented_packet_feed() {
     common::init_tracing();
@@ -509,3 +557,46 @@ fn test_multi_packet_in_one_feed() {
         _ => panic!("Expected Incomplete result after reading all packets"),
     }
 }
+
+#[test]
+fn test_publish_assembled_from_fragments_standalone() {
+    common::init_tracing();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(b"payload A")
+        .build()
+        .unwrap();
+    let bytes = publish.to_continuous_buffer();
+
+    let mut builder = mqtt::connection::PacketBuilder::new();
+
+    // Feed one byte at a time; only the last byte completes the packet
+    let (last, fragments) = bytes.split_last().unwrap();
+    for i in 0..fragments.len() {
+        let mut cursor = mqtt::common::Cursor::new(&fragments[i..i + 1]);
+        match builder.feed(&mut cursor) {
+            mqtt::connection::PacketBuildResult::Incomplete => (),
+            _ => panic!("Expected Incomplete result at byte {i}"),
+        }
+    }
+    let last = [*last];
+    let mut cursor = mqtt::common::Cursor::new(&last[..]);
+    match builder.feed(&mut cursor) {
+        mqtt::connection::PacketBuildResult::Complete(packet) => {
+            assert_eq!(packet.packet_type(), 3); // PUBLISH
+            assert!(packet.is_publish());
+            assert_eq!(packet.remaining_length() as usize, bytes.len() - 2);
+            assert_eq!(packet.data_as_slice(), &bytes[2..]);
+
+            let mqtt::connection::PacketData::Publish(arc) = packet.data.clone() else {
+                panic!("Expected PacketData::Publish");
+            };
+            let (parsed, _) = mqtt::packet::v5_0::Publish::parse(packet.flags(), arc).unwrap();
+            assert_eq!(parsed, publish);
+        }
+        _ => panic!("Expected Complete result after final byte"),
+    }
+}
@@ -0,0 +1,78 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use mqtt_protocol_core::mqtt;
+mod common;
+
+#[test]
+fn export_import_ranges_round_trip() {
+    common::init_tracing();
+    let mut pid_man = mqtt::connection::PacketIdManager::<u32>::new();
+    for _ in 0..10 {
+        pid_man.acquire_unique_id().unwrap();
+    }
+    for id in [2, 3, 7] {
+        pid_man.release_id(id);
+    }
+    pid_man.register_id(100).unwrap();
+    pid_man.register_id(101).unwrap();
+    pid_man.register_id(u32::MAX).unwrap();
+
+    let ranges = pid_man.export_ranges();
+    assert_eq!(
+        ranges,
+        vec![(1, 1), (4, 6), (8, 10), (100, 101), (u32::MAX, u32::MAX)]
+    );
+
+    let mut imported = mqtt::connection::PacketIdManager::<u32>::new();
+    imported.register_id(50).unwrap();
+    imported.import_ranges(&ranges).unwrap();
+    assert_eq!(imported.export_ranges(), ranges);
+    for id in [1, 4, 5, 6, 8, 9, 10, 100, 101, u32::MAX] {
+        assert!(imported.is_used_id(id));
+    }
+    for id in [2, 3, 7, 11, 50, 99, 102] {
+        assert!(!imported.is_used_id(id));
+    }
+    assert_eq!(imported.acquire_unique_id(), Ok(2));
+}
+
+#[test]
+fn import_ranges_error() {
+    common::init_tracing();
+    let mut pid_man = mqtt::connection::PacketIdManager::<u16>::new();
+    pid_man.register_id(1).unwrap();
+
+    assert_eq!(
+        pid_man.import_ranges(&[(0, 3)]),
+        Err(mqtt::result_code::MqttError::PacketIdentifierInvalid)
+    );
+    assert_eq!(
+        pid_man.import_ranges(&[(5, 4)]),
+        Err(mqtt::result_code::MqttError::PacketIdentifierInvalid)
+    );
+    assert_eq!(
+        pid_man.import_ranges(&[(2, 5), (5, 6)]),
+        Err(mqtt::result_code::MqttError::PacketIdentifierConflict)
+    );
+    // The state is unchanged on error
+    assert_eq!(pid_man.export_ranges(), vec![(1, 1)]);
+}
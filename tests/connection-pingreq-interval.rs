@@ -178,3 +178,43 @@ fn test_set_pingreq_send_interval_server_keep_alive() {
         panic!("Expected NotifyPacketReceived event, got: {:?}", events[1]);
     }
 }
+
+#[test]
+fn test_next_timer_deadline() {
+    common::init_tracing();
+    let mut connection =
+        mqtt::GenericConnection::<mqtt::role::Client, u16>::new(mqtt::Version::V5_0);
+    assert_eq!(connection.next_timer_deadline(), None);
+
+    v5_0_client_establish_connection(&mut connection);
+    assert_eq!(connection.next_timer_deadline(), None);
+
+    // PingreqSend is armed
+    connection.set_pingreq_send_interval(Some(30000));
+    assert_eq!(
+        connection.next_timer_deadline(),
+        Some((mqtt::connection::TimerKind::PingreqSend, 30000))
+    );
+
+    // PingrespRecv is armed as well and is shorter
+    connection.set_pingresp_recv_timeout(5000);
+    let pingreq = mqtt::packet::v5_0::Pingreq::builder().build().unwrap();
+    connection.checked_send(pingreq);
+    assert_eq!(
+        connection.next_timer_deadline(),
+        Some((mqtt::connection::TimerKind::PingrespRecv, 5000))
+    );
+
+    // PINGRESP cancels PingrespRecv
+    let pingresp = mqtt::packet::v5_0::Pingresp::builder().build().unwrap();
+    let bytes = pingresp.to_continuous_buffer();
+    connection.recv(&mut mqtt::common::Cursor::new(&bytes));
+    assert_eq!(
+        connection.next_timer_deadline(),
+        Some((mqtt::connection::TimerKind::PingreqSend, 30000))
+    );
+
+    // Disabling PingreqSend leaves no armed timer
+    connection.set_pingreq_send_interval(Some(0));
+    assert_eq!(connection.next_timer_deadline(), None);
+}
@@ -0,0 +1,107 @@
+// MIT License
+//
+// Copyright (c) 2025 Takatoshi Kondo
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+use mqtt_protocol_core::mqtt;
+use mqtt_protocol_core::mqtt::prelude::*;
+use std::cell::Cell;
+mod common;
+
+// Packet that counts how many times it has been encoded
+struct CountingPacket {
+    bytes: Vec<u8>,
+    encode_count: Cell<usize>,
+}
+
+impl CountingPacket {
+    fn new(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            encode_count: Cell::new(0),
+        }
+    }
+}
+
+impl GenericPacketTrait for CountingPacket {
+    fn size(&self) -> usize {
+        self.bytes.len()
+    }
+
+    fn to_continuous_buffer(&self) -> Vec<u8> {
+        self.encode_count.set(self.encode_count.get() + 1);
+        self.bytes.clone()
+    }
+
+    fn raw_flags(&self) -> u8 {
+        self.bytes[0] & 0b0000_1111
+    }
+
+    #[cfg(feature = "std")]
+    fn to_buffers(&self) -> Vec<std::io::IoSlice<'_>> {
+        vec![std::io::IoSlice::new(&self.bytes)]
+    }
+}
+
+#[test]
+fn encode_once() {
+    common::init_tracing();
+    let mut cached = mqtt::packet::CachedPacket::new(CountingPacket::new(vec![0xC0, 0x00]));
+    assert!(!cached.is_cached());
+    assert_eq!(cached.packet().encode_count.get(), 0);
+
+    let first = cached.to_continuous_buffer_cached().to_vec();
+    let second = cached.to_continuous_buffer_cached().to_vec();
+    assert_eq!(first, second);
+    assert!(cached.is_cached());
+    assert_eq!(cached.packet().encode_count.get(), 1);
+}
+
+#[test]
+fn invalidate_on_mutation() {
+    common::init_tracing();
+    let mut cached = mqtt::packet::CachedPacket::new(CountingPacket::new(vec![0xC0, 0x00]));
+    assert_eq!(cached.to_continuous_buffer_cached(), &[0xC0, 0x00]);
+
+    cached.packet_mut().bytes = vec![0xD0, 0x00];
+    assert!(!cached.is_cached());
+    assert_eq!(cached.to_continuous_buffer_cached(), &[0xD0, 0x00]);
+    assert_eq!(cached.packet().encode_count.get(), 2);
+}
+
+#[test]
+fn same_bytes_as_packet() {
+    common::init_tracing();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .retain(true)
+        .payload(b"retained")
+        .build()
+        .unwrap();
+    let expected = publish.to_continuous_buffer();
+
+    let mut cached = mqtt::packet::CachedPacket::new(publish.clone());
+    assert_eq!(cached.to_continuous_buffer_cached(), expected.as_slice());
+
+    let packet: mqtt::packet::Packet = publish.into();
+    let mut cached: mqtt::packet::CachedPacket<_> = packet.into();
+    assert_eq!(cached.to_continuous_buffer_cached(), expected.as_slice());
+    assert_eq!(cached.into_inner().to_continuous_buffer(), expected);
+}
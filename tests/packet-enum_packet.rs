@@ -525,3 +525,55 @@ fn test_generic_packet_packet_type_v5_0() {
     let packet: mqtt::packet::GenericPacket<u16> = mqtt::packet::GenericPacket::V5_0Auth(auth);
     assert_eq!(packet.packet_type(), mqtt::packet::PacketType::Auth);
 }
+
+#[test]
+fn test_generic_packet_raw_flags_parsed_subscribe() {
+    common::init_tracing();
+    let entry =
+        mqtt::packet::SubEntry::new("test/topic", mqtt::packet::SubOpts::default()).unwrap();
+    let original = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(1u16)
+        .entries(vec![entry])
+        .build()
+        .unwrap();
+    let continuous = original.to_continuous_buffer();
+    assert_eq!(continuous[0], 0x82);
+
+    let (parsed, _) = mqtt::packet::v5_0::Subscribe::parse(&continuous[2..]).unwrap();
+    assert_eq!(parsed.raw_flags(), 0b0010);
+
+    let packet = mqtt::packet::Packet::V5_0Subscribe(parsed);
+    assert_eq!(packet.raw_flags(), 0b0010);
+}
+
+#[test]
+fn test_generic_packet_raw_flags() {
+    common::init_tracing();
+    let pubrel = mqtt::packet::v3_1_1::Pubrel::builder()
+        .packet_id(1u16)
+        .build()
+        .unwrap();
+    assert_eq!(pubrel.raw_flags(), 0b0010);
+
+    let unsubscribe = mqtt::packet::v3_1_1::Unsubscribe::builder()
+        .packet_id(1u16)
+        .entries(vec!["test/topic"])
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(unsubscribe.raw_flags(), 0b0010);
+
+    let pingreq = mqtt::packet::v3_1_1::Pingreq::new();
+    assert_eq!(pingreq.raw_flags(), 0b0000);
+
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test/topic")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .retain(true)
+        .dup(true)
+        .build()
+        .unwrap();
+    assert_eq!(publish.raw_flags(), 0b1101);
+}
@@ -1093,3 +1093,39 @@ fn test_property_enum_parsing() {
         _ => panic!("Expected SharedSubscriptionAvailable"),
     }
 }
+
+#[test]
+fn test_properties_parse_overlong_length() {
+    use mqtt::packet::PropertiesParse;
+    common::init_tracing();
+    // Property Length 0 must be encoded as a single byte
+    let (props, consumed) = mqtt::packet::Properties::parse(&[0x00]).unwrap();
+    assert!(props.is_empty());
+    assert_eq!(consumed, 1);
+
+    let result = mqtt::packet::Properties::parse(&[0x80, 0x00]);
+    assert_eq!(
+        result.unwrap_err(),
+        mqtt::result_code::MqttError::MalformedPacket
+    );
+}
+
+#[test]
+fn test_subscription_identifier_parse_overlong_value() {
+    common::init_tracing();
+    // Subscription Identifier 1 must be encoded as a single byte
+    let (prop, consumed) = mqtt::packet::Property::parse(&[0x0B, 0x01]).unwrap();
+    assert_eq!(
+        prop,
+        mqtt::packet::Property::SubscriptionIdentifier(
+            mqtt::packet::SubscriptionIdentifier::new(1).unwrap()
+        )
+    );
+    assert_eq!(consumed, 2);
+
+    let result = mqtt::packet::Property::parse(&[0x0B, 0x81, 0x00]);
+    assert_eq!(
+        result.unwrap_err(),
+        mqtt::result_code::MqttError::MalformedPacket
+    );
+}
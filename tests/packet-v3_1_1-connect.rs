@@ -681,3 +681,18 @@ fn test_packet_type() {
     let packet_type = mqtt::packet::v3_1_1::Connect::packet_type();
     assert_eq!(packet_type, mqtt::packet::PacketType::Connect);
 }
+
+#[test]
+fn parse_invalid_reserved_flag() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0x00, 0x04, b'M', b'Q', b'T', b'T']); // protocol name
+    data.push(0x04); // version
+    data.push(0x03); // flags (clean + reserved bit set)
+    data.extend_from_slice(&[0x00, 0x3C]); // keep alive
+    data.extend_from_slice(&[0x00, 0x04]); // client id length
+    data.extend_from_slice(b"test"); // client id
+
+    let err = mqtt::packet::v3_1_1::Connect::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
+}
@@ -19,6 +19,7 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+use mqtt::packet::BuildWithin;
 use mqtt_protocol_core::mqtt;
 
 mod common;
@@ -134,6 +135,39 @@ fn build_fail_payload_too_large() {
 
 // Build success tests
 
+#[test]
+fn build_within_fail_packet_too_large() {
+    common::init_tracing();
+    let err = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(vec![0u8; 1024 * 1024])
+        .build_within(1024)
+        .unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::PacketTooLarge);
+}
+
+#[test]
+fn build_within_success_exact_size() {
+    common::init_tracing();
+    let packet = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .payload(b"payload")
+        .build()
+        .unwrap();
+    let size = packet.size();
+    let built = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .payload(b"payload")
+        .build_within(size)
+        .unwrap();
+    assert_eq!(built, packet);
+}
+
 #[test]
 fn build_success_qos2() {
     common::init_tracing();
@@ -760,3 +794,24 @@ fn test_qos0_without_packet_id_success() {
         .unwrap();
     assert_eq!(result.packet_id(), None);
 }
+
+#[test]
+fn into_message() {
+    common::init_tracing();
+    let publish = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .retain(true)
+        .payload(b"payload")
+        .build()
+        .unwrap();
+
+    let message = publish.into_message();
+    assert_eq!(message.topic_name, "topic/a");
+    assert_eq!(message.payload.as_slice(), b"payload");
+    assert_eq!(message.qos, mqtt::packet::Qos::AtLeastOnce);
+    assert!(message.retain);
+    assert!(message.props.is_empty());
+}
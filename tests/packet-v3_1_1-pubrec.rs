@@ -157,6 +157,19 @@ fn parse_invalid_too_short() {
     assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
 }
 
+#[test]
+fn parse_pid0() {
+    common::init_tracing();
+    let data = [0x00, 0x00]; // Packet ID 0
+    let err = mqtt::packet::v3_1_1::Pubrec::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
+
+    // Packet ID 0 is rejected for u32 packet IDs as well
+    let data = [0x00, 0x00, 0x00, 0x00];
+    let err = mqtt::packet::v3_1_1::GenericPubrec::<u32>::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
+}
+
 // Size tests
 #[test]
 fn size_minimal() {
@@ -323,6 +323,17 @@ fn parse_multiple_entries() {
     assert_eq!(parsed.entries()[2].topic_filter().to_string(), "topic3");
 }
 
+#[test]
+fn parse_invalid_packet_id_zero() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&(0u16).to_be_bytes()); // packet ID
+    data.extend_from_slice(&[0x00, 0x01, b'a', 0x00]); // topic filter and QoS
+
+    let err = mqtt::packet::v3_1_1::Subscribe::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
 #[test]
 fn parse_invalid_too_short() {
     common::init_tracing();
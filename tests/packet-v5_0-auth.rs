@@ -484,6 +484,36 @@ fn parse_rc_proplen_over() {
     assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
 }
 
+#[test]
+fn parse_auth_method_data() {
+    common::init_tracing();
+    let raw = &[
+        0x18, // reason code: ContinueAuthentication
+        0x14, // property length 20
+        0x15, // Authentication Method
+        0x00, 0x0B, b'S', b'C', b'R', b'A', b'M', b'-', b'S', b'H', b'A', b'-', b'1',
+        0x16, // Authentication Data
+        0x00, 0x03, 0x01, 0x02, 0x03,
+    ];
+    let (packet, consumed) = mqtt::packet::v5_0::Auth::parse(raw).unwrap();
+    assert_eq!(consumed, raw.len());
+    assert_eq!(
+        packet.reason_code(),
+        Some(mqtt::result_code::AuthReasonCode::ContinueAuthentication)
+    );
+    assert_eq!(packet.authentication_method(), Some("SCRAM-SHA-1"));
+    assert_eq!(packet.authentication_data(), Some(&[0x01, 0x02, 0x03][..]));
+}
+
+#[test]
+fn parse_empty_no_auth_method_data() {
+    common::init_tracing();
+    let (packet, _) = mqtt::packet::v5_0::Auth::parse(&[]).unwrap();
+    assert_eq!(packet.reason_code(), None);
+    assert_eq!(packet.authentication_method(), None);
+    assert_eq!(packet.authentication_data(), None);
+}
+
 #[test]
 fn parse_rc_prop_auth_method() {
     common::init_tracing();
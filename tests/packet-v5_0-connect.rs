@@ -1098,6 +1098,29 @@ fn parse_with_will() {
     assert_eq!(parsed.will_payload().unwrap(), b"will_payload");
 }
 
+#[test]
+fn parse_invalid_will_property() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0x00, 0x04]);
+    data.extend_from_slice(b"MQTT");
+    data.push(0x05); // version
+    data.push(0x06); // connect flags: clean start, will flag
+    data.extend_from_slice(&[0x00, 0x00]); // keep alive
+    data.push(0x00); // property length
+    data.extend_from_slice(&[0x00, 0x01, b'a']); // client id
+    data.extend_from_slice(&[0x02, 0x0B, 0x01]); // will props: SubscriptionIdentifier(1)
+    data.extend_from_slice(&[0x00, 0x01, b't']); // will topic
+    data.extend_from_slice(&[0x00, 0x00]); // will payload
+    let err = mqtt::packet::v5_0::Connect::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+
+    // The same CONNECT with an allowed will property is accepted
+    data[15] = 0x01; // PayloadFormatIndicator(1)
+    let (parsed, _) = mqtt::packet::v5_0::Connect::parse(&data).unwrap();
+    assert_eq!(parsed.will_topic().unwrap(), "t");
+}
+
 #[test]
 fn parse_invalid_too_short() {
     common::init_tracing();
@@ -1405,3 +1428,19 @@ fn test_packet_type() {
     let packet_type = mqtt::packet::v5_0::Connect::packet_type();
     assert_eq!(packet_type, mqtt::packet::PacketType::Connect);
 }
+
+#[test]
+fn parse_invalid_reserved_flag() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&[0x00, 0x04, b'M', b'Q', b'T', b'T']); // protocol name
+    data.push(0x05); // version
+    data.push(0x03); // flags (clean + reserved bit set)
+    data.extend_from_slice(&[0x00, 0x3C]); // keep alive
+    data.push(0x00); // property length
+    data.extend_from_slice(&[0x00, 0x04]); // client id length
+    data.extend_from_slice(b"test"); // client id
+
+    let err = mqtt::packet::v5_0::Connect::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
+}
@@ -19,6 +19,7 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+use mqtt::packet::BuildWithin;
 use mqtt_protocol_core::mqtt;
 
 mod common;
@@ -100,6 +101,37 @@ fn build_fail_valid_prop_server_reference_twice() {
     assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
 }
 
+// Build within tests
+
+#[test]
+fn build_within_fail_packet_too_large() {
+    common::init_tracing();
+    let err = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .props(vec![mqtt::packet::ReasonString::new("Server maintenance")
+            .unwrap()
+            .into()])
+        .build_within(16)
+        .unwrap_err();
+
+    assert_eq!(err, mqtt::result_code::MqttError::PacketTooLarge);
+}
+
+#[test]
+fn build_within_success_exact_size() {
+    common::init_tracing();
+    // fixed header(2) + reason code(1) + property length(1) + ReasonString(1 + 2 + 18)
+    let packet = mqtt::packet::v5_0::Disconnect::builder()
+        .reason_code(mqtt::result_code::DisconnectReasonCode::ServerShuttingDown)
+        .props(vec![mqtt::packet::ReasonString::new("Server maintenance")
+            .unwrap()
+            .into()])
+        .build_within(25)
+        .unwrap();
+
+    assert_eq!(packet.size(), 25);
+}
+
 // Display tests
 
 #[test]
@@ -488,6 +488,20 @@ fn parse_pid_rc_prop_unmatched() {
     assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
 }
 
+#[test]
+fn parse_pid_rc_prop_topic_alias() {
+    common::init_tracing();
+    let mut raw = Vec::from(1234u16.to_be_bytes());
+    raw.push(0x00); // reason code: Success
+    raw.push(0x03); // property length
+    raw.push(0x23); // property ID: Topic Alias (0x23)
+    raw.push(0x00); // topic alias MSB
+    raw.push(0x01); // topic alias LSB
+
+    let err = mqtt::packet::v5_0::Puback::parse(&raw).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
 #[test]
 fn parse_pid_rc_prop_user_property_twice() {
     common::init_tracing();
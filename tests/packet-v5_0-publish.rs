@@ -19,6 +19,7 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+use mqtt::packet::BuildWithin;
 use mqtt_protocol_core::mqtt;
 
 mod common;
@@ -88,6 +89,39 @@ fn build_fail_qos0_with_packet_id() {
     assert_eq!(err, mqtt::result_code::MqttError::MalformedPacket);
 }
 
+#[test]
+fn build_within_fail_packet_too_large() {
+    common::init_tracing();
+    let err = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(vec![0u8; 1024 * 1024])
+        .build_within(1024)
+        .unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::PacketTooLarge);
+}
+
+#[test]
+fn build_within_success_exact_size() {
+    common::init_tracing();
+    let packet = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .payload(b"payload")
+        .build()
+        .unwrap();
+    let size = packet.size();
+    let built = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test")
+        .unwrap()
+        .payload(b"payload")
+        .build_within(size)
+        .unwrap();
+    assert_eq!(built, packet);
+}
+
 #[test]
 fn build_fail_duplicate_property() {
     common::init_tracing();
@@ -1596,3 +1630,127 @@ fn test_qos0_without_packet_id_success() {
         .unwrap();
     assert_eq!(result.packet_id(), None);
 }
+
+#[test]
+fn test_reply_to() {
+    common::init_tracing();
+    let request = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("service/request")
+        .unwrap()
+        .qos(mqtt::packet::Qos::AtLeastOnce)
+        .packet_id(1u16)
+        .payload(b"request")
+        .props(vec![
+            mqtt::packet::ResponseTopic::new("service/response")
+                .unwrap()
+                .into(),
+            mqtt::packet::CorrelationData::new(b"12345").unwrap().into(),
+            mqtt::packet::ContentType::new("text/plain").unwrap().into(),
+        ])
+        .build()
+        .unwrap();
+
+    let reply = mqtt::packet::v5_0::Publish::reply_to(&request, b"response").unwrap();
+    assert_eq!(reply.topic_name(), "service/response");
+    assert_eq!(reply.qos(), mqtt::packet::Qos::AtMostOnce);
+    assert_eq!(reply.payload().as_slice(), b"response");
+    assert_eq!(
+        *reply.props(),
+        vec![mqtt::packet::CorrelationData::new(b"12345").unwrap().into()]
+    );
+}
+
+#[test]
+fn test_reply_to_without_response_topic() {
+    common::init_tracing();
+    let request = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("service/request")
+        .unwrap()
+        .props(vec![mqtt::packet::CorrelationData::new(b"12345")
+            .unwrap()
+            .into()])
+        .build()
+        .unwrap();
+
+    let err = mqtt::packet::v5_0::Publish::reply_to(&request, b"response").unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::TopicNameInvalid);
+}
+
+#[test]
+fn test_subscription_identifiers() {
+    common::init_tracing();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test/topic")
+        .unwrap()
+        .payload(b"payload")
+        .props(vec![
+            mqtt::packet::SubscriptionIdentifier::new(1).unwrap().into(),
+            mqtt::packet::ContentType::new("text/plain").unwrap().into(),
+            mqtt::packet::SubscriptionIdentifier::new(268_435_455)
+                .unwrap()
+                .into(),
+        ])
+        .build()
+        .unwrap();
+
+    let bytes = publish.to_continuous_buffer();
+    let data_arc: Arc<[u8]> = Arc::from(&bytes[2..]);
+    let (parsed, _) = mqtt::packet::v5_0::Publish::parse(bytes[0] & 0x0F, data_arc).unwrap();
+    assert_eq!(parsed.subscription_identifiers(), vec![1, 268_435_455]);
+}
+
+#[test]
+fn test_subscription_identifiers_none() {
+    common::init_tracing();
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("test/topic")
+        .unwrap()
+        .build()
+        .unwrap();
+    assert!(publish.subscription_identifiers().is_empty());
+}
+
+#[test]
+fn into_message() {
+    common::init_tracing();
+    let props = vec![
+        mqtt::packet::ContentType::new("text/plain").unwrap().into(),
+        mqtt::packet::UserProperty::new("key", "value")
+            .unwrap()
+            .into(),
+    ];
+    let publish = mqtt::packet::v5_0::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .payload(b"payload")
+        .props(props.clone())
+        .build()
+        .unwrap();
+
+    let message = publish.into_message();
+    assert_eq!(message.topic_name, "topic/a");
+    assert_eq!(message.payload.as_slice(), b"payload");
+    assert_eq!(message.qos, mqtt::packet::Qos::ExactlyOnce);
+    assert!(!message.retain);
+    assert_eq!(message.props, props);
+
+    // The same message published by v3.1.1 differs only in properties
+    let v3_1_1_message = mqtt::packet::v3_1_1::Publish::builder()
+        .topic_name("topic/a")
+        .unwrap()
+        .qos(mqtt::packet::Qos::ExactlyOnce)
+        .packet_id(1u16)
+        .payload(b"payload")
+        .build()
+        .unwrap()
+        .into_message();
+    assert_eq!(
+        v3_1_1_message,
+        mqtt::packet::Message {
+            props: Vec::new(),
+            ..message
+        }
+    );
+}
@@ -472,6 +472,18 @@ fn parse_multiple_entries() {
     assert_eq!(parsed.entries()[2].topic_filter(), "topic3");
 }
 
+#[test]
+fn parse_invalid_packet_id_zero() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&(0u16).to_be_bytes()); // packet ID
+    data.push(0x00); // property length
+    data.extend_from_slice(&[0x00, 0x01, b'a', 0x00]); // topic filter and options
+
+    let err = mqtt::packet::v5_0::Subscribe::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
 #[test]
 fn parse_invalid_too_short() {
     common::init_tracing();
@@ -504,6 +516,46 @@ fn parse_invalid_property() {
     assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
 }
 
+#[test]
+fn parse_invalid_duplicate_subscription_identifier() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&(1u16).to_be_bytes()); // packet ID
+    data.push(0x04); // property length = 4
+    data.push(0x0B); // SubscriptionIdentifier
+    data.push(0x01); // value = 1
+    data.push(0x0B); // SubscriptionIdentifier (duplicated)
+    data.push(0x02); // value = 2
+    data.extend_from_slice(&(1u16).to_be_bytes()); // topic filter length
+    data.push(b'a'); // topic filter
+    data.push(0x00); // subscription options
+
+    let err = mqtt::packet::v5_0::Subscribe::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
+#[test]
+fn build_fail_duplicate_subscription_identifier() {
+    common::init_tracing();
+    let mut props = mqtt::packet::Properties::new();
+    props.push(mqtt::packet::Property::SubscriptionIdentifier(
+        mqtt::packet::SubscriptionIdentifier::new(1).unwrap(),
+    ));
+    props.push(mqtt::packet::Property::SubscriptionIdentifier(
+        mqtt::packet::SubscriptionIdentifier::new(2).unwrap(),
+    ));
+
+    let entry =
+        mqtt::packet::SubEntry::new("test/topic", mqtt::packet::SubOpts::default()).unwrap();
+    let err = mqtt::packet::v5_0::Subscribe::builder()
+        .packet_id(1u16)
+        .entries(vec![entry])
+        .props(props)
+        .build()
+        .unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
 #[test]
 fn parse_shared_subscription_valid_share_name() {
     common::init_tracing();
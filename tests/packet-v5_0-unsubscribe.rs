@@ -433,6 +433,18 @@ fn parse_multiple_entries() {
     assert_eq!(parsed.entries()[2].as_str(), "topic3");
 }
 
+#[test]
+fn parse_invalid_packet_id_zero() {
+    common::init_tracing();
+    let mut data = Vec::new();
+    data.extend_from_slice(&(0u16).to_be_bytes()); // packet ID
+    data.push(0x00); // property length
+    data.extend_from_slice(&[0x00, 0x01, b'a']); // topic filter
+
+    let err = mqtt::packet::v5_0::Unsubscribe::parse(&data).unwrap_err();
+    assert_eq!(err, mqtt::result_code::MqttError::ProtocolError);
+}
+
 #[test]
 fn parse_invalid_too_short() {
     common::init_tracing();
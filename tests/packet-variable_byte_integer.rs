@@ -199,3 +199,48 @@ fn test_max_constant() {
     let result = VariableByteInteger::from_u32(VariableByteInteger::MAX + 1);
     assert!(result.is_none());
 }
+
+#[test]
+fn test_decode_strict_minimal_encoding() {
+    common::init_tracing();
+    let (vbi, consumed) = VariableByteInteger::decode_strict(&[0x00]).unwrap();
+    assert_eq!(vbi.to_u32(), 0);
+    assert_eq!(consumed, 1);
+
+    let (vbi, consumed) = VariableByteInteger::decode_strict(&[0x80, 0x01]).unwrap();
+    assert_eq!(vbi.to_u32(), 128);
+    assert_eq!(consumed, 2);
+}
+
+#[test]
+fn test_decode_strict_overlong_encoding() {
+    common::init_tracing();
+    // 0 encoded in 2 bytes
+    assert_eq!(
+        VariableByteInteger::decode_strict(&[0x80, 0x00]).unwrap_err(),
+        mqtt_protocol_core::mqtt::result_code::MqttError::MalformedPacket
+    );
+    // 127 encoded in 3 bytes
+    assert_eq!(
+        VariableByteInteger::decode_strict(&[0xFF, 0x80, 0x00]).unwrap_err(),
+        mqtt_protocol_core::mqtt::result_code::MqttError::MalformedPacket
+    );
+
+    // Non-strict decoding accepts the overlong encoding
+    let (vbi, consumed) = VariableByteInteger::decode(&[0x80, 0x00]).unwrap();
+    assert_eq!(vbi.to_u32(), 0);
+    assert_eq!(consumed, 2);
+}
+
+#[test]
+fn test_decode_errors() {
+    common::init_tracing();
+    assert_eq!(
+        VariableByteInteger::decode_strict(&[0x80, 0x80]).unwrap_err(),
+        mqtt_protocol_core::mqtt::result_code::MqttError::InsufficientBytes
+    );
+    assert_eq!(
+        VariableByteInteger::decode(&[0x80, 0x80, 0x80, 0x80, 0x01]).unwrap_err(),
+        mqtt_protocol_core::mqtt::result_code::MqttError::MalformedPacket
+    );
+}
@@ -185,6 +185,23 @@ fn test_mqtt_error_display() {
     );
     assert_eq!(format!("{}", MqttError::ValueOutOfRange), "ValueOutOfRange");
     assert_eq!(format!("{}", MqttError::InvalidQos), "InvalidQos");
+    assert_eq!(format!("{}", MqttError::AckQosMismatch), "AckQosMismatch");
+    assert_eq!(
+        format!("{}", MqttError::DisconnectReasonCodeNotAllowed),
+        "DisconnectReasonCodeNotAllowed"
+    );
+    assert_eq!(
+        format!("{}", MqttError::KeepAliveBelowMinimum),
+        "KeepAliveBelowMinimum"
+    );
+    assert_eq!(
+        format!("{}", MqttError::OptionalPropertiesTooLarge),
+        "OptionalPropertiesTooLarge"
+    );
+    assert_eq!(
+        format!("{}", MqttError::PubrecNotReceived),
+        "PubrecNotReceived"
+    );
 }
 
 #[test]